@@ -7,5 +7,26 @@ pub enum Action {
     PlaceQuote(Quote),
     CancelQuote(Quote),
     EndRound,
-    EndGame
+    EndGame,
+    // 暂停期间拒绝新挂单，交易计时冻结
+    Pause,
+    Resume,
+    // 桌面聊天；to 为空时发给全桌，否则只发给对方（发送者也会收到一份）
+    Chat {
+        player_id: String,
+        to: Option<String>,
+        text: String,
+    },
+}
+
+
+impl Action {
+    // 发起该动作的玩家，系统动作（开始/结束回合等）没有玩家
+    pub fn player_id(&self) -> Option<&str> {
+        match self {
+            Action::PlaceQuote(quote) | Action::CancelQuote(quote) => Some(&quote.player_id),
+            Action::Chat { player_id, .. } => Some(player_id),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,52 @@
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// 时间来源，返回自 UNIX epoch 起经过的时间
+// 生产环境使用 SystemClock，测试和模拟器使用 ManualClock 手动推进时间
+pub trait Clock: Send + Sync + fmt::Debug {
+    fn now(&self) -> Duration;
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        // 系统时间早于 epoch 时退化为 0，不让引擎因时钟异常崩溃
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+}
+
+// 只有调用 set/advance 才会前进的时钟，精度为毫秒
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now_millis: AtomicU64,
+}
+
+impl ManualClock {
+    pub fn new(start: Duration) -> Self {
+        ManualClock {
+            now_millis: AtomicU64::new(start.as_millis() as u64),
+        }
+    }
+
+    pub fn set(&self, now: Duration) {
+        self.now_millis.store(now.as_millis() as u64, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now_millis.fetch_add(by.as_millis() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_millis(self.now_millis.load(Ordering::SeqCst))
+    }
+}
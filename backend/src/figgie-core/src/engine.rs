@@ -1,17 +1,74 @@
 use crate::types::*;
 use crate::action::*;
 use crate::event::*;
+use crate::error::*;
+use crate::clock::*;
 use crate::utils::*;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use std::sync::Arc;
+use std::time::Duration;
 use rand;
 use rand::seq::SliceRandom;
 
 pub const CARD_VALUE_PER_GOAL_SUIT: i32 = 10;
 const MAX_QUOTE_PRICE: u32 = 100;
 const MIN_QUOTE_PRICE: u32 = 1;
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 7;
+pub const DEFAULT_ROUNDS: u32 = 4;
+
+// 牌堆构成：common 花色最多，另有两个普通花色和一个短花色
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DeckConfig {
+    pub common_count: u8,
+    pub regular_count: u8,
+    pub short_count: u8,
+}
+
+impl Default for DeckConfig {
+    // 标准 Figgie：12/10/10/8，共 40 张
+    fn default() -> Self {
+        DeckConfig {
+            common_count: 12,
+            regular_count: 10,
+            short_count: 8,
+        }
+    }
+}
+
+impl DeckConfig {
+    pub fn size(&self) -> usize {
+        self.common_count as usize + 2 * self.regular_count as usize + self.short_count as usize
+    }
+}
+
+// 牌数不能被玩家数整除时，余下的牌如何处理
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum LeftoverPolicy {
+    // 余牌不发，本轮不参与游戏
+    #[default]
+    Discard,
+    // 余牌依次多发给座位靠前的玩家，手牌数可能不一致
+    DealExtra,
+}
+
+impl FromStr for LeftoverPolicy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Discard" => Ok(LeftoverPolicy::Discard),
+            "DealExtra" => Ok(LeftoverPolicy::DealExtra),
+            _ => Err("invalid leftover policy"),
+        }
+    }
+}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct GameConfig {
     pub room_name: String,
     pub room_id: String,
@@ -20,44 +77,271 @@ pub struct GameConfig {
     pub ante_amount: u32,
     pub pot: u32,
     pub trading_duration_secs: u32,
+    // 一局的回合数；没打满就结束的对局结果不算完整
+    #[serde(default = "default_rounds")]
+    pub rounds: u32,
+    pub deck: DeckConfig,
+    pub leftover_policy: LeftoverPolicy,
+}
+
+fn default_rounds() -> u32 {
+    DEFAULT_ROUNDS
+}
+
+impl GameConfig {
+    pub fn validate(&self) -> Result<(), FiggieError> {
+        let num_players = self.players.len();
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&num_players) {
+            return Err(FiggieError::InvalidPlayerCount(num_players));
+        }
+
+        let mut ids = HashSet::new();
+        if let Some(dup) = self.players.iter().find(|p| !ids.insert(p.id.as_str())) {
+            return Err(FiggieError::DuplicatePlayer(dup.id.clone()));
+        }
+
+        // common 花色必须唯一且最多，goal 花色才能确定
+        let deck = &self.deck;
+        if deck.short_count == 0
+            || deck.regular_count < deck.short_count
+            || deck.common_count <= deck.regular_count
+            || deck.size() < num_players {
+            return Err(FiggieError::InvalidDeck);
+        }
+
+        // 底池必须正好由底注凑齐：pot 不能被人数整除时，余数由第一个座位补上
+        let players = num_players as u32;
+        let antes = self.ante_amount.checked_mul(players);
+        if antes.is_none_or(|antes| antes > self.pot || self.pot - antes >= players) {
+            return Err(FiggieError::AnteMismatch {
+                ante: self.ante_amount,
+                pot: self.pot,
+                players: num_players,
+            });
+        }
+
+        if self.ante_amount + self.odd_chips() > self.starting_cash {
+            return Err(FiggieError::AnteExceedsCash {
+                ante: self.ante_amount + self.odd_chips(),
+                starting_cash: self.starting_cash,
+            });
+        }
+
+        if self.rounds == 0 {
+            return Err(FiggieError::InvalidRoundCount(self.rounds));
+        }
+
+        Ok(())
+    }
+
+    // pot 平摊后除不尽的部分，由第一个座位额外支付
+    pub fn odd_chips(&self) -> u32 {
+        self.pot.saturating_sub(self.ante_amount.saturating_mul(self.players.len() as u32))
+    }
 }
 
 #[derive(Debug)]
 pub struct Game {
     pub round: u32,
+    pub phase: Phase,
     pub game_config: GameConfig,
     pub common_suit: Suit,
     pub goal_suit: Suit,
+    // 本轮牌堆中每种花色的牌数（标准为 12/10/10/8，包含未发出的余牌）
+    pub suit_counts: HashMap<Suit, u8>,
+    // 本轮发牌后、交易前每个玩家的手牌
+    pub starting_hands: HashMap<String, Hand>,
     pub state: GameState,
+    // 本轮开始的时间，用于计算交易剩余时间
+    pub round_started_at: Duration,
+    // 暂停的时刻，恢复时把暂停的时长从计时中扣除
+    pub paused_at: Option<Duration>,
+    // 交易时间走完才结算的回合数；提前结束的回合不算，回合编号由客户端给出也不能用
+    pub full_rounds: u32,
+    clock: SharedClock,
+}
+
+// 可序列化的对局快照，停机时落盘、重启后恢复
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub round: u32,
+    pub phase: Phase,
+    pub game_config: GameConfig,
+    pub common_suit: Suit,
+    pub goal_suit: Suit,
+    pub suit_counts: HashMap<Suit, u8>,
+    pub starting_hands: HashMap<String, Hand>,
+    pub state: GameState,
+    // 本轮已经进行的时间，恢复后交易计时从这里继续
+    pub round_elapsed_millis: u64,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub full_rounds: u32,
 }
 
 impl Game {
-    pub fn new(config: GameConfig) -> Self {
+    pub fn new(config: GameConfig) -> Result<Self, FiggieError> {
+        Self::with_clock(config, Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(config: GameConfig, clock: SharedClock) -> Result<Self, FiggieError> {
+        config.validate()?;
+
         // 玩家初始化
         let num_players = config.players.len();
         let mut players: Vec<Player> = Vec::with_capacity(num_players);
-        for info in config.players.clone() {
+        for (seat, info) in config.players.clone().into_iter().enumerate() {
+            let ante = if seat == 0 { config.ante_amount + config.odd_chips() } else { config.ante_amount };
             players.push(Player {
                 info: Info{ id: info.id, name: info.name},
-                cash: (config.starting_cash - config.ante_amount) as i32,
+                cash: (config.starting_cash - ante) as i32,
                 hand: Hand { cards: HashMap::new() },
             });
         }
 
-        Game {
+        log::info!("[room {}] game created with {} players", config.room_id, num_players);
+        Ok(Game {
             round: 1,
+            phase: Phase::Waiting,
             game_config: config,
             // 这两个初始花色会被后续覆盖掉
             common_suit: Suit::Heart,
             goal_suit: Suit::Diamond,
+            suit_counts: HashMap::new(),
+            starting_hands: HashMap::new(),
             state: GameState {
                 players,
                 quotes: vec![]
-            }
+            },
+            round_started_at: clock.now(),
+            paused_at: None,
+            full_rounds: 0,
+            clock,
+        })
+    }
+
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot {
+            round: self.round,
+            phase: self.phase,
+            game_config: self.game_config.clone(),
+            common_suit: self.common_suit,
+            goal_suit: self.goal_suit,
+            suit_counts: self.suit_counts.clone(),
+            starting_hands: self.starting_hands.clone(),
+            state: self.state.clone(),
+            round_elapsed_millis: self.round_elapsed().as_millis() as u64,
+            paused: self.is_paused(),
+            full_rounds: self.full_rounds,
+        }
+    }
+
+    pub fn restore(snapshot: GameSnapshot, clock: SharedClock) -> Result<Self, FiggieError> {
+        snapshot.game_config.validate()?;
+        let expected = snapshot.game_config.players.iter().map(|p| &p.id);
+        if !expected.eq(snapshot.state.players.iter().map(|p| &p.info.id)) {
+            return Err(FiggieError::Internal("snapshot players do not match room config"));
+        }
+
+        log::info!(
+            "[room {}] game restored at round {} ({:?})",
+            snapshot.game_config.room_id, snapshot.round, snapshot.phase
+        );
+        let now = clock.now();
+        let round_started_at = now.saturating_sub(Duration::from_millis(snapshot.round_elapsed_millis));
+        Ok(Game {
+            round: snapshot.round,
+            phase: snapshot.phase,
+            game_config: snapshot.game_config,
+            common_suit: snapshot.common_suit,
+            goal_suit: snapshot.goal_suit,
+            suit_counts: snapshot.suit_counts,
+            starting_hands: snapshot.starting_hands,
+            state: snapshot.state,
+            round_started_at,
+            paused_at: snapshot.paused.then_some(now),
+            full_rounds: snapshot.full_rounds,
+            clock,
+        })
+    }
+
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+
+    // 暂停期间停在暂停的时刻
+    pub fn round_elapsed(&self) -> Duration {
+        self.paused_at
+            .unwrap_or_else(|| self.clock.now())
+            .saturating_sub(self.round_started_at)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    // 只能在交易阶段暂停；重复暂停不产生事件
+    pub fn pause(&mut self) -> Result<Vec<Event>, FiggieError> {
+        self.expect_phase(&[Phase::Trading])?;
+        if self.is_paused() {
+            return Ok(vec![]);
+        }
+        let now = self.clock.now();
+        self.paused_at = Some(now);
+        log::info!("[room {}] trading paused in round {}", self.room_id(), self.round);
+        Ok(vec![Event::TradingPaused {
+            round_id: self.round as u8,
+            server_time: now.as_secs(),
+            remaining_millis: self.round_time_remaining().as_millis() as u64,
+        }])
+    }
+
+    pub fn resume(&mut self) -> Result<Vec<Event>, FiggieError> {
+        self.expect_phase(&[Phase::Trading])?;
+        let Some(paused_at) = self.paused_at.take() else {
+            return Ok(vec![]);
+        };
+        let now = self.clock.now();
+        // 回合开始时间顺延暂停的时长
+        self.round_started_at += now.saturating_sub(paused_at);
+        log::info!("[room {}] trading resumed in round {}", self.room_id(), self.round);
+        Ok(vec![Event::TradingResumed {
+            round_id: self.round as u8,
+            server_time: now.as_secs(),
+            remaining_millis: self.round_time_remaining().as_millis() as u64,
+        }])
+    }
+
+    // 本轮交易剩余时间，回合未进行时为 0
+    pub fn round_time_remaining(&self) -> Duration {
+        if self.phase != Phase::Trading {
+            return Duration::ZERO;
+        }
+        Duration::from_secs(self.game_config.trading_duration_secs as u64)
+            .saturating_sub(self.round_elapsed())
+    }
+
+    pub fn room_id(&self) -> &str {
+        &self.game_config.room_id
+    }
+
+    pub fn player(&self, player_id: &str) -> Result<&Player, FiggieError> {
+        self.state.players
+            .iter()
+            .find(|p| p.info.id == player_id)
+            .ok_or_else(|| FiggieError::UnknownPlayer(player_id.to_string()))
+    }
+
+    fn expect_phase(&self, allowed: &[Phase]) -> Result<(), FiggieError> {
+        if allowed.contains(&self.phase) {
+            Ok(())
+        } else {
+            Err(FiggieError::WrongPhase(self.phase))
         }
     }
 
-    pub fn handle_action(&mut self, action: Action) -> Vec<Event> {
+    pub fn handle_action(&mut self, action: Action) -> Result<Vec<Event>, FiggieError> {
         match action {
             Action::PlaceQuote(quote) => {
                 self.place_quote(quote)
@@ -78,41 +362,77 @@ impl Game {
             Action::EndGame => {
                 self.end_game()
             }
+
+            Action::Pause => {
+                self.pause()
+            }
+
+            Action::Resume => {
+                self.resume()
+            }
+
+            Action::Chat { player_id, to, text } => {
+                self.chat(player_id, to, text)
+            }
         }
     }
 
-    pub fn place_quote(&mut self, quote: Quote) -> Vec<Event> {
+    // 聊天不受阶段限制；长度、频率和内容过滤由服务端负责
+    pub fn chat(&mut self, from: String, to: Option<String>, text: String) -> Result<Vec<Event>, FiggieError> {
+        self.player(&from)?;
+        if let Some(to) = &to {
+            self.player(to)?;
+        }
+        if text.trim().is_empty() {
+            return Err(FiggieError::EmptyMessage);
+        }
+        Ok(vec![Event::ChatMessage {
+            from,
+            to,
+            text,
+            server_time: self.clock.now().as_secs(),
+        }])
+    }
+
+    pub fn place_quote(&mut self, quote: Quote) -> Result<Vec<Event>, FiggieError> {
+        self.expect_phase(&[Phase::Trading])?;
+        if self.is_paused() {
+            return Err(FiggieError::Paused);
+        }
+
         if quote.price < MIN_QUOTE_PRICE || quote.price > MAX_QUOTE_PRICE {
-            return vec![];
+            return Err(FiggieError::InvalidPrice(quote.price));
         }
 
-        if quote.side == Side::Offer {
-            // 卖出时，保证手里有对应 suit 的牌
-            if self.state.players
-                .iter()
-                .find(|p| p.info.id == quote.player_id)
-                .and_then(|p| p.hand.cards.get(&quote.suit).copied())
-                .unwrap_or(0) == 0 {
-                return vec![];
-            }
+        let player = self.player(&quote.player_id)?;
+        // 卖出时，保证手里有对应 suit 的牌
+        if quote.side == Side::Offer && player.hand.cards.get(&quote.suit).copied().unwrap_or(0) == 0 {
+            return Err(FiggieError::InsufficientCards {
+                player_id: quote.player_id,
+                suit: quote.suit,
+            });
         }
+
         // 尝试撮合
         if let Some(idx) = find_matching_quote_idx(&self.state.quotes, &quote) {
-            let matched = self.state.quotes.remove(idx);
-
             let (buyer, seller) = match quote.side {
-                Side::Bid => (quote.player_id.clone(), matched.player_id.clone()),
-                Side::Offer => (matched.player_id.clone(), quote.player_id.clone()),
+                Side::Bid => (quote.player_id.clone(), self.state.quotes[idx].player_id.clone()),
+                Side::Offer => (self.state.quotes[idx].player_id.clone(), quote.player_id.clone()),
             };
 
-            self.apply_trade(&buyer, &seller, quote.suit, quote.price);
+            self.apply_trade(&buyer, &seller, quote.suit, quote.price)?;
+            self.state.quotes.remove(idx);
 
-            return vec![Event::TradeExecuted {
+            log::info!(
+                "[room {}] trade executed: {} buys {} from {} at {}",
+                self.room_id(), buyer, quote.suit, seller, quote.price
+            );
+            return Ok(vec![Event::TradeExecuted {
                 buyer,
                 seller,
                 suit: quote.suit,
                 price: quote.price,
-            }];
+            }]);
         }
 
         // 没有撮合，检查是否替换现有 quote
@@ -136,38 +456,50 @@ impl Game {
 
         if replaced || !self.state.quotes.iter().any(|q| q.suit == quote.suit && q.side == quote.side) {
             self.state.quotes.push(quote.clone());
-            println!("Engine receive the action: {:?}", quote.clone());
-            vec![Event::QuotePlaced {
+            log::debug!("[room {}] quote placed by {}: {:?}", self.room_id(), quote.player_id, quote);
+            Ok(vec![Event::QuotePlaced {
                 quote,
-            }]
+            }])
         } else {
-            vec![]
+            Err(FiggieError::QuoteNotCompetitive)
         }
     }
 
     fn apply_trade(
         &mut self,
-        buyer_id: &String,
-        seller_id: &String,
+        buyer_id: &str,
+        seller_id: &str,
         suit: Suit,
         price: u32,
-    ) {
+    ) -> Result<(), FiggieError> {
+        // 先校验双方，保证失败时不会只修改一方
+        self.player(buyer_id)?;
+        if self.player(seller_id)?.hand.cards.get(&suit).copied().unwrap_or(0) == 0 {
+            return Err(FiggieError::InsufficientCards {
+                player_id: seller_id.to_string(),
+                suit,
+            });
+        }
+
         let price = price as i32;
 
         for player in &mut self.state.players {
-            if player.info.id == *buyer_id {
+            if player.info.id == buyer_id {
                 player.cash -= price;
                 *player.hand.cards.entry(suit).or_insert(0) += 1;
             }
 
-            if player.info.id == *seller_id {
+            if player.info.id == seller_id {
                 player.cash += price;
                 *player.hand.cards.entry(suit).or_insert(0) -= 1;
             }
         }
+        Ok(())
     }
 
-    pub fn cancel_quote(&mut self, quote: Quote) -> Vec<Event> {
+    pub fn cancel_quote(&mut self, quote: Quote) -> Result<Vec<Event>, FiggieError> {
+        self.expect_phase(&[Phase::Trading])?;
+
         let before = self.state.quotes.len();
 
         self.state.quotes.retain(|q| {
@@ -178,36 +510,56 @@ impl Game {
         });
 
         if self.state.quotes.len() == before {
-            return vec![];
+            return Err(FiggieError::QuoteNotFound);
         }
 
-        println!("Quote Cancelled");
-        vec![Event::QuoteCanceled {
+        log::debug!("[room {}] quote cancelled by {}: {:?}", self.room_id(), quote.player_id, quote);
+        Ok(vec![Event::QuoteCanceled {
             quote,
-        }]
+        }])
     }
 
-    pub fn start_round(&mut self, round: u32) -> Vec<Event> {
-        self.round = round;
+    // 撤掉某个玩家的全部挂单，例如玩家掉线时
+    pub fn cancel_player_quotes(&mut self, player_id: &str) -> Vec<Event> {
+        let (cancelled, kept): (Vec<Quote>, Vec<Quote>) = self.state.quotes
+            .drain(..)
+            .partition(|q| q.player_id == player_id);
+        self.state.quotes = kept;
+
+        if !cancelled.is_empty() {
+            log::info!("[room {}] cancelled {} quotes of {}", self.room_id(), cancelled.len(), player_id);
+        }
+        cancelled.into_iter().map(|quote| Event::QuoteCanceled { quote }).collect()
+    }
+
+    pub fn start_round(&mut self, round: u32) -> Result<Vec<Event>, FiggieError> {
+        self.expect_phase(&[Phase::Waiting, Phase::RoundOver])?;
+        let now = self.clock.now();
 
         // 重新生成 common_suit 和 goal_suit
         let suits = [Suit::Spade, Suit::Heart, Suit::Diamond, Suit::Club];
         let mut rng = rand::thread_rng();
 
-        // 随机分配 suit 长度：哪两个10, 哪个8, 哪个12
-        let mut suit_types = vec![10u8, 10u8, 8u8, 12u8];
+        // 随机分配 suit 长度：哪两个 regular, 哪个 short, 哪个 common
+        let deck_config = self.game_config.deck;
+        let mut suit_types = [
+            deck_config.regular_count,
+            deck_config.regular_count,
+            deck_config.short_count,
+            deck_config.common_count,
+        ];
         suit_types.shuffle(&mut rng);
         let mut suit_card_counts = HashMap::new();
         for (s, n) in suits.iter().zip(suit_types.iter()) {
             suit_card_counts.insert(*s, *n);
         }
 
-        // 找到12张的作为common
+        // 找到张数最多的作为common
         let common_suit = suit_card_counts
             .iter()
-            .find(|(_, num)| **num == 12)
+            .find(|(_, num)| **num == deck_config.common_count)
             .map(|(suit, _)| *suit)
-            .unwrap();
+            .ok_or(FiggieError::Internal("no suit has the common count"))?;
 
         // goal suit：与common同色的另一花色
         fn is_red(s: Suit) -> bool {
@@ -218,19 +570,19 @@ impl Game {
         }
         let goal_suit = suits
             .iter()
-            .filter(|&&s| {
+            .find(|&&s| {
                 s != common_suit &&
                 ((is_red(s) && is_red(common_suit)) || (is_black(s) && is_black(common_suit)))
             })
-            .next()
             .copied()
-            .unwrap();
+            .ok_or(FiggieError::Internal("no suit shares the common suit's colour"))?;
 
+        self.round = round;
         self.common_suit = common_suit;
         self.goal_suit = goal_suit;
 
         // 重新发牌
-        let mut deck: Vec<Suit> = Vec::with_capacity(40);
+        let mut deck: Vec<Suit> = Vec::with_capacity(deck_config.size());
         for (suit, count) in &suit_card_counts {
             for _ in 0..*count {
                 deck.push(*suit);
@@ -244,6 +596,11 @@ impl Game {
             player.hand.cards.clear();
         }
 
+        // 不能整除时按配置处理余牌
+        if self.game_config.leftover_policy == LeftoverPolicy::Discard {
+            deck.truncate(deck.len() - deck.len() % num_players);
+        }
+
         // 重新发牌
         for (i, suit) in deck.iter().enumerate() {
             let pidx = i % num_players;
@@ -251,41 +608,53 @@ impl Game {
             *player.hand.cards.entry(*suit).or_insert(0) += 1;
         }
 
+        // 记录本轮的发牌结果，回合结束时公布；弃掉的余牌不计入
+        let mut dealt_counts: HashMap<Suit, u8> = suit_card_counts.keys().map(|suit| (*suit, 0)).collect();
+        for suit in &deck {
+            *dealt_counts.entry(*suit).or_insert(0) += 1;
+        }
+        self.suit_counts = dealt_counts;
+        self.starting_hands = self.state.players
+            .iter()
+            .map(|p| (p.info.id.clone(), p.hand.clone()))
+            .collect();
+
         // 清空 quotes
         self.state.quotes.clear();
+        self.phase = Phase::Trading;
+        self.round_started_at = now;
+        self.paused_at = None;
 
+        log::info!("[room {}] round {} started", self.room_id(), round);
         // 返回 RoundStarted 事件，为每个玩家
-        let server_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        println!("Round {} Started", round);
-        self.state.players.iter().map(|player| {
+        Ok(self.state.players.iter().map(|player| {
             Event::RoundStarted {
                 round_id: round as u8,
-                server_time,
+                server_time: now.as_secs(),
                 player: player.clone(),
             }
-        }).collect()
+        }).collect())
     }
 
-    pub fn end_round(&mut self) -> Vec<Event> {
+    pub fn end_round(&mut self) -> Result<Vec<Event>, FiggieError> {
+        self.expect_phase(&[Phase::Trading])?;
+        // 暂停中计时不走，要先恢复才能结束回合
+        if self.is_paused() {
+            return Err(FiggieError::Paused);
+        }
+        let server_time = self.clock.now().as_secs();
+        let full_round = self.round_time_remaining().is_zero();
+
         let goal = self.goal_suit;
         let pot = self.game_config.pot as i32; // 转换为 i32 以匹配 cash
 
         // 计算每个玩家的 goal_suit 数量
-        let mut player_goal_counts: HashMap<String, u8> = HashMap::new();
-        for player in &self.state.players {
-            let count = player.hand.cards.get(&goal).copied().unwrap_or(0);
-            player_goal_counts.insert(player.info.id.clone(), count);
-        }
+        let goal_count = |player: &Player| player.hand.cards.get(&goal).copied().unwrap_or(0);
 
         // 发放 bonus：每张 goal suit +10 cash
         let mut total_bonus = 0i32;
         for player in &mut self.state.players {
-            let goal_count = player_goal_counts[&player.info.id];
-            let bonus = (goal_count as i32) * CARD_VALUE_PER_GOAL_SUIT;
+            let bonus = (goal_count(player) as i32) * CARD_VALUE_PER_GOAL_SUIT;
             player.cash += bonus;
             total_bonus += bonus;
         }
@@ -294,38 +663,57 @@ impl Game {
         let rest_pot = if pot > total_bonus { pot - total_bonus } else { 0 };
 
         // 找到拥有最多 goal_suit 的玩家
-        let max_goal = player_goal_counts.values().copied().max().unwrap_or(0);
-        let winners: Vec<String> = player_goal_counts.iter()
-            .filter(|(_, cnt)| **cnt == max_goal && max_goal > 0)
-            .map(|(pid, _)| pid.clone())
+        let max_goal = self.state.players.iter().map(goal_count).max().unwrap_or(0);
+        let winners: Vec<String> = self.state.players.iter()
+            .filter(|p| max_goal > 0 && goal_count(p) == max_goal)
+            .map(|p| p.info.id.clone())
             .collect();
 
         // 平分剩余 pot
         let share = if !winners.is_empty() { rest_pot / winners.len() as i32 } else { 0 };
 
-        for winner_id in &winners {
-            if let Some(player) = self.state.players.iter_mut().find(|p| p.info.id == *winner_id) {
+        for player in &mut self.state.players {
+            if winners.contains(&player.info.id) {
                 player.cash += share;
             }
         }
 
-        println!("Round {} Ended", self.round);
+        self.state.quotes.clear();
+        self.phase = Phase::RoundOver;
+        if full_round {
+            self.full_rounds += 1;
+        }
+
+        log::info!(
+            "[room {}] round {} ended, goal suit {}, winners {:?}",
+            self.room_id(), self.round, goal, winners
+        );
         // 返回事件
-        vec![Event::RoundEnded {
+        Ok(vec![Event::RoundEnded {
             round_id: self.round as u8,
             players: self.state.players.clone(),
-            server_time: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            server_time,
             goal_suit: goal,
-        }]
+            common_suit: self.common_suit,
+            suit_counts: self.suit_counts.clone(),
+            starting_hands: self.starting_hands.clone(),
+        }])
     }
 
-    pub fn end_game(&mut self) -> Vec<Event> {
-        println!("Game Ended");
-        vec![Event::GameEnded {
+    pub fn end_game(&mut self) -> Result<Vec<Event>, FiggieError> {
+        if self.phase == Phase::GameOver {
+            return Err(FiggieError::WrongPhase(self.phase));
+        }
+        let completed = self.full_rounds >= self.game_config.rounds;
+        self.phase = Phase::GameOver;
+        self.paused_at = None;
+
+        log::info!(
+            "[room {}] game ended after {} of {} full rounds", self.room_id(), self.full_rounds, self.game_config.rounds
+        );
+        Ok(vec![Event::GameEnded {
             players: self.state.players.clone(),
-        }]
+            completed,
+        }])
     }
 }
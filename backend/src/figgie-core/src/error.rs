@@ -0,0 +1,87 @@
+use crate::types::{Suit, Phase};
+
+use std::fmt;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FiggieError {
+    // 创建对局时的配置错误
+    InvalidPlayerCount(usize),
+    DuplicatePlayer(String),
+    InvalidDeck,
+    AnteExceedsCash { ante: u32, starting_cash: u32 },
+    AnteMismatch { ante: u32, pot: u32, players: usize },
+    InvalidRoundCount(u32),
+
+    // 玩家动作被拒绝
+    UnknownPlayer(String),
+    InvalidPrice(u32),
+    InsufficientCards { player_id: String, suit: Suit },
+    QuoteNotCompetitive,
+    QuoteNotFound,
+    WrongPhase(Phase),
+    // 交易暂停期间不能挂新单
+    Paused,
+
+    // 聊天被拒绝；长度、限流和内容过滤由服务端负责
+    EmptyMessage,
+
+    // 引擎内部状态不一致，正常情况下不会出现
+    Internal(&'static str),
+}
+
+impl fmt::Display for FiggieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FiggieError::InvalidPlayerCount(n) => {
+                write!(f, "Figgie requires 2 to 7 players, got {n}")
+            }
+            FiggieError::DuplicatePlayer(id) => write!(f, "duplicate player id: {id}"),
+            FiggieError::InvalidDeck => {
+                write!(f, "deck must satisfy common > regular >= short > 0 and have at least one card per player")
+            }
+            FiggieError::AnteExceedsCash { ante, starting_cash } => {
+                write!(f, "ante {ante} exceeds starting cash {starting_cash}")
+            }
+            FiggieError::AnteMismatch { ante, pot, players } => {
+                write!(f, "ante {ante} from {players} players does not add up to pot {pot}")
+            }
+            FiggieError::InvalidRoundCount(n) => write!(f, "a game needs at least one round, got {n}"),
+            FiggieError::UnknownPlayer(id) => write!(f, "unknown player: {id}"),
+            FiggieError::InvalidPrice(price) => write!(f, "price {price} out of range"),
+            FiggieError::InsufficientCards { player_id, suit } => {
+                write!(f, "player {player_id} holds no {suit} to offer")
+            }
+            FiggieError::QuoteNotCompetitive => write!(f, "quote does not improve the current best"),
+            FiggieError::QuoteNotFound => write!(f, "quote not found"),
+            FiggieError::WrongPhase(phase) => write!(f, "action not allowed in phase {phase:?}"),
+            FiggieError::Paused => write!(f, "trading is paused"),
+            FiggieError::EmptyMessage => write!(f, "message is empty"),
+            FiggieError::Internal(msg) => write!(f, "internal engine error: {msg}"),
+        }
+    }
+}
+
+impl FiggieError {
+    // 稳定的错误码，供客户端按类型处理
+    pub fn code(&self) -> &'static str {
+        match self {
+            FiggieError::InvalidPlayerCount(_) => "InvalidPlayerCount",
+            FiggieError::DuplicatePlayer(_) => "DuplicatePlayer",
+            FiggieError::InvalidDeck => "InvalidDeck",
+            FiggieError::AnteExceedsCash { .. } => "AnteExceedsCash",
+            FiggieError::AnteMismatch { .. } => "AnteMismatch",
+            FiggieError::InvalidRoundCount(_) => "InvalidRoundCount",
+            FiggieError::UnknownPlayer(_) => "UnknownPlayer",
+            FiggieError::InvalidPrice(_) => "InvalidPrice",
+            FiggieError::InsufficientCards { .. } => "InsufficientCards",
+            FiggieError::QuoteNotCompetitive => "QuoteNotCompetitive",
+            FiggieError::QuoteNotFound => "QuoteNotFound",
+            FiggieError::WrongPhase(_) => "WrongPhase",
+            FiggieError::Paused => "Paused",
+            FiggieError::EmptyMessage => "EmptyMessage",
+            FiggieError::Internal(_) => "Internal",
+        }
+    }
+}
+
+impl std::error::Error for FiggieError {}
@@ -1,6 +1,11 @@
-use crate::types::{Suit, Player, Quote};
+use crate::types::{Suit, Player, Quote, Hand};
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 
-#[derive(Clone, Debug)]
+// 线上格式为 {"type": "TradeExecuted", "payload": {...}}
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", content = "payload")]
 pub enum Event {
     TradeExecuted {
         buyer: String,
@@ -24,9 +29,45 @@ pub enum Event {
         players: Vec<Player>,
         server_time: u64,
         goal_suit: Suit,
+        common_suit: Suit,
+        // 本轮实际发出的每种花色的牌数，不含弃掉的余牌
+        suit_counts: HashMap<Suit, u8>,
+        // 每个玩家的初始手牌，key 为 player id
+        starting_hands: HashMap<String, Hand>,
     },
     GameEnded {
         players: Vec<Player>,
+        // 是否打满了配置的回合数
+        completed: bool,
+    },
+    // 玩家连接断开（心跳超时或主动关闭）
+    PlayerDisconnected {
+        player_id: String,
+    },
+    PlayerReconnected {
+        player_id: String,
+    },
+    // 交易暂停 / 恢复；remaining_millis 为本轮剩余的交易时间
+    TradingPaused {
+        round_id: u8,
+        server_time: u64,
+        remaining_millis: u64,
+    },
+    TradingResumed {
+        round_id: u8,
+        server_time: u64,
+        remaining_millis: u64,
+    },
+    // 聊天消息；to 为空表示全桌可见
+    ChatMessage {
+        from: String,
+        to: Option<String>,
+        text: String,
+        server_time: u64,
+    },
+    // 服务器即将停机；restorable 表示房间已保存，重启后可以重新连接继续
+    ServerShutdown {
+        restorable: bool,
     },
 }
 
@@ -34,7 +75,16 @@ impl Event {
     pub fn target_player(&self) -> Option<&String> {
         match self {
             Event::RoundStarted { player, .. } => Some(&player.info.id),
+            Event::ChatMessage { to: Some(to), .. } => Some(to),
             _ => None, 
         }
     }
+
+    // 定向事件之外还需要收到一份的玩家，例如私聊的发送者
+    pub fn copy_to(&self) -> Option<&String> {
+        match self {
+            Event::ChatMessage { from, to: Some(to), .. } if to != from => Some(from),
+            _ => None,
+        }
+    }
 }
\ No newline at end of file
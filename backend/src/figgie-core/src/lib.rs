@@ -3,10 +3,14 @@ pub mod types;
 pub mod action;
 pub mod engine;
 pub mod event;
+pub mod error;
+pub mod clock;
 mod utils;
 
 // 对外暴露的“核心概念”
 pub use types::*;
 pub use action::*;
 pub use engine::*;
-pub use event::*;
\ No newline at end of file
+pub use event::*;
+pub use error::*;
+pub use clock::*;
\ No newline at end of file
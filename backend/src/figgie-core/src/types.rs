@@ -1,12 +1,14 @@
 use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Info {
     pub id: String,
     pub name: String,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, JsonSchema)]
 pub enum Suit {
     Spade,
     Heart,
@@ -14,25 +16,27 @@ pub enum Suit {
     Club,
 }
 
-#[derive(Clone, Debug, Default)]
+// 序列化为 {"Spade": 3, ...}
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
 pub struct Hand {
     pub cards: HashMap<Suit, u8>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Player {
     pub info: Info,
     pub hand: Hand,
     pub cash: i32,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum Side {
     Bid,
     Offer,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Quote {
     pub player_id: String,
     pub suit: Suit,
@@ -40,13 +44,24 @@ pub struct Quote {
     pub price: u32,
 }
 
-#[derive(Clone, Debug)]
+// 对局所处阶段
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum Phase {
+    // 已创建，尚未开始第一轮
+    Waiting,
+    Trading,
+    RoundOver,
+    GameOver,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct GameState {
     pub players: Vec<Player>,
     pub quotes: Vec<Quote>,
 }
 
 use std::fmt;
+use std::str::FromStr;
 
 impl fmt::Display for Suit {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -70,8 +85,10 @@ impl fmt::Display for Side {
     }
 }
 
-impl Suit {
-    pub fn from_str(s: &str) -> Result<Self, &'static str> {
+impl FromStr for Suit {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "Spade" => Ok(Suit::Spade),
             "Heart" => Ok(Suit::Heart),
@@ -82,8 +99,10 @@ impl Suit {
     }
 }
 
-impl Side {
-    pub fn from_str(s: &str) -> Result<Self, &'static str> {
+impl FromStr for Side {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "Bid" => Ok(Side::Bid),
             "Offer" => Ok(Side::Offer),
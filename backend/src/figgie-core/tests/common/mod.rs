@@ -0,0 +1,76 @@
+#![allow(dead_code)]
+
+use figgie_core::*;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const START: Duration = Duration::from_secs(1_700_000_000);
+
+pub fn player_id(i: usize) -> String {
+    format!("p{i}")
+}
+
+pub fn config(num_players: usize) -> GameConfig {
+    GameConfig {
+        room_name: "test room".to_string(),
+        room_id: "R-TEST".to_string(),
+        players: (0..num_players).map(|i| Info {
+            id: player_id(i),
+            name: format!("Player {i}"),
+        }).collect(),
+        starting_cash: 350,
+        ante_amount: 200u32.checked_div(num_players as u32).unwrap_or(0),
+        pot: 200,
+        trading_duration_secs: 240,
+        deck: DeckConfig::default(),
+        leftover_policy: LeftoverPolicy::Discard,
+        rounds: DEFAULT_ROUNDS,
+    }
+}
+
+pub fn new_game(config: GameConfig) -> (Game, Arc<ManualClock>) {
+    let clock = Arc::new(ManualClock::new(START));
+    let game = Game::with_clock(config, clock.clone()).expect("valid config");
+    (game, clock)
+}
+
+// 开始第一轮后，用给定的手牌覆盖随机发牌，便于断言
+pub fn trading_game(hands: &[[u8; 4]]) -> Game {
+    let (mut game, _) = new_game(config(hands.len()));
+    game.start_round(1).expect("round starts");
+    for (player, counts) in game.state.players.iter_mut().zip(hands) {
+        player.hand = hand(*counts);
+    }
+    game
+}
+
+// 顺序为 Spade, Heart, Diamond, Club
+pub fn hand(counts: [u8; 4]) -> Hand {
+    let suits = [Suit::Spade, Suit::Heart, Suit::Diamond, Suit::Club];
+    Hand {
+        cards: suits.into_iter().zip(counts).collect::<HashMap<_, _>>(),
+    }
+}
+
+pub fn quote(player: usize, suit: Suit, side: Side, price: u32) -> Quote {
+    Quote {
+        player_id: player_id(player),
+        suit,
+        side,
+        price,
+    }
+}
+
+pub fn cards(game: &Game, player: usize, suit: Suit) -> u8 {
+    game.state.players[player].hand.cards.get(&suit).copied().unwrap_or(0)
+}
+
+pub fn total_cards(hand: &Hand) -> u32 {
+    hand.cards.values().map(|&c| c as u32).sum()
+}
+
+pub fn is_red(suit: Suit) -> bool {
+    matches!(suit, Suit::Heart | Suit::Diamond)
+}
@@ -0,0 +1,563 @@
+mod common;
+
+use common::*;
+use figgie_core::*;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn new_validates_player_count() {
+    for n in [0, 1, 8] {
+        let err = Game::new(config(n)).unwrap_err();
+        assert_eq!(err, FiggieError::InvalidPlayerCount(n));
+    }
+    for n in MIN_PLAYERS..=MAX_PLAYERS {
+        assert!(Game::new(config(n)).is_ok(), "{n} players should be accepted");
+    }
+}
+
+#[test]
+fn new_rejects_invalid_config() {
+    let mut dup = config(4);
+    dup.players[3].id = player_id(0);
+    assert_eq!(Game::new(dup).unwrap_err(), FiggieError::DuplicatePlayer(player_id(0)));
+
+    let mut deck = config(4);
+    deck.deck.common_count = deck.deck.regular_count;
+    assert_eq!(Game::new(deck).unwrap_err(), FiggieError::InvalidDeck);
+
+    let mut ante = config(4);
+    ante.ante_amount = ante.starting_cash + 1;
+    ante.pot = ante.ante_amount * 4;
+    assert!(matches!(Game::new(ante).unwrap_err(), FiggieError::AnteExceedsCash { .. }));
+
+    // 底注凑不齐或超出底池都不行
+    let mut short = config(3);
+    short.ante_amount = 60;
+    assert_eq!(Game::new(short).unwrap_err(), FiggieError::AnteMismatch { ante: 60, pot: 200, players: 3 });
+    let mut over = config(4);
+    over.ante_amount = 51;
+    assert!(matches!(Game::new(over).unwrap_err(), FiggieError::AnteMismatch { .. }));
+}
+
+#[test]
+fn new_collects_ante() {
+    let (game, _) = new_game(config(5));
+    assert_eq!(game.phase, Phase::Waiting);
+    assert!(game.state.players.iter().all(|p| p.cash == 310));
+}
+
+// 不能整除时余数由第一个座位支付，底注合计正好等于 pot
+#[test]
+fn antes_add_up_to_pot_for_uneven_tables() {
+    for (num_players, first, rest) in [(3, 282, 284), (7, 318, 322)] {
+        let config = config(num_players);
+        let (game, _) = new_game(config.clone());
+        let cash: Vec<i32> = game.state.players.iter().map(|p| p.cash).collect();
+        assert_eq!(cash[0], first);
+        assert!(cash[1..].iter().all(|&c| c == rest));
+        let antes: i32 = cash.iter().map(|c| config.starting_cash as i32 - c).sum();
+        assert_eq!(antes, config.pot as i32);
+    }
+}
+
+#[test]
+fn quotes_rejected_outside_trading() {
+    let (mut game, _) = new_game(config(4));
+    let err = game.place_quote(quote(0, Suit::Spade, Side::Bid, 5)).unwrap_err();
+    assert_eq!(err, FiggieError::WrongPhase(Phase::Waiting));
+
+    game.start_round(1).unwrap();
+    game.end_round().unwrap();
+    let err = game.place_quote(quote(0, Suit::Spade, Side::Bid, 5)).unwrap_err();
+    assert_eq!(err, FiggieError::WrongPhase(Phase::RoundOver));
+    assert_eq!(game.end_round().unwrap_err(), FiggieError::WrongPhase(Phase::RoundOver));
+}
+
+#[test]
+fn place_quote_rests_on_empty_book() {
+    let mut game = trading_game(&[[3, 3, 2, 2]; 4]);
+    let q = quote(0, Suit::Heart, Side::Bid, 7);
+    let events = game.place_quote(q.clone()).unwrap();
+
+    assert!(matches!(&events[..], [Event::QuotePlaced { quote }] if quote.price == 7));
+    assert_eq!(game.state.quotes.len(), 1);
+}
+
+#[test]
+fn place_quote_validates_price_and_player() {
+    let mut game = trading_game(&[[3, 3, 2, 2]; 4]);
+    for price in [0, 101] {
+        let err = game.place_quote(quote(0, Suit::Heart, Side::Bid, price)).unwrap_err();
+        assert_eq!(err, FiggieError::InvalidPrice(price));
+    }
+
+    let mut stranger = quote(0, Suit::Heart, Side::Bid, 5);
+    stranger.player_id = "nobody".to_string();
+    assert_eq!(game.place_quote(stranger).unwrap_err(), FiggieError::UnknownPlayer("nobody".to_string()));
+    assert!(game.state.quotes.is_empty());
+}
+
+#[test]
+fn offer_requires_cards() {
+    let mut game = trading_game(&[[0, 4, 3, 3], [3, 3, 2, 2], [3, 3, 2, 2], [3, 3, 2, 2]]);
+    let err = game.place_quote(quote(0, Suit::Spade, Side::Offer, 9)).unwrap_err();
+    assert_eq!(err, FiggieError::InsufficientCards { player_id: player_id(0), suit: Suit::Spade });
+}
+
+#[test]
+fn better_quote_replaces_worse_one() {
+    let mut game = trading_game(&[[3, 3, 2, 2]; 4]);
+    game.place_quote(quote(0, Suit::Club, Side::Bid, 5)).unwrap();
+    game.place_quote(quote(1, Suit::Club, Side::Bid, 6)).unwrap();
+
+    let bids: Vec<_> = game.state.quotes.iter().filter(|q| q.side == Side::Bid).collect();
+    assert_eq!(bids.len(), 1);
+    assert_eq!(bids[0].player_id, player_id(1));
+    assert_eq!(bids[0].price, 6);
+
+    game.place_quote(quote(2, Suit::Club, Side::Offer, 12)).unwrap();
+    game.place_quote(quote(3, Suit::Club, Side::Offer, 10)).unwrap();
+    let offers: Vec<_> = game.state.quotes.iter().filter(|q| q.side == Side::Offer).collect();
+    assert_eq!(offers.len(), 1);
+    assert_eq!(offers[0].player_id, player_id(3));
+}
+
+#[test]
+fn worse_or_equal_quote_is_not_competitive() {
+    let mut game = trading_game(&[[3, 3, 2, 2]; 4]);
+    game.place_quote(quote(0, Suit::Club, Side::Bid, 5)).unwrap();
+
+    for price in [4, 5] {
+        let err = game.place_quote(quote(1, Suit::Club, Side::Bid, price)).unwrap_err();
+        assert_eq!(err, FiggieError::QuoteNotCompetitive);
+    }
+    assert_eq!(game.state.quotes.len(), 1);
+    assert_eq!(game.state.quotes[0].player_id, player_id(0));
+}
+
+#[test]
+fn quotes_on_other_suits_and_sides_coexist() {
+    let mut game = trading_game(&[[3, 3, 2, 2]; 4]);
+    game.place_quote(quote(0, Suit::Club, Side::Bid, 5)).unwrap();
+    game.place_quote(quote(0, Suit::Club, Side::Offer, 9)).unwrap();
+    game.place_quote(quote(1, Suit::Spade, Side::Bid, 5)).unwrap();
+    assert_eq!(game.state.quotes.len(), 3);
+}
+
+#[test]
+fn matching_quote_executes_trade() {
+    let mut game = trading_game(&[[3, 3, 2, 2]; 4]);
+    game.place_quote(quote(0, Suit::Diamond, Side::Bid, 8)).unwrap();
+    let events = game.place_quote(quote(1, Suit::Diamond, Side::Offer, 8)).unwrap();
+
+    match &events[..] {
+        [Event::TradeExecuted { buyer, seller, suit, price }] => {
+            assert_eq!(buyer, &player_id(0));
+            assert_eq!(seller, &player_id(1));
+            assert_eq!(*suit, Suit::Diamond);
+            assert_eq!(*price, 8);
+        }
+        other => panic!("unexpected events: {other:?}"),
+    }
+    assert!(game.state.quotes.is_empty());
+    assert_eq!(cards(&game, 0, Suit::Diamond), 3);
+    assert_eq!(cards(&game, 1, Suit::Diamond), 1);
+    assert_eq!(game.state.players[0].cash, 300 - 8);
+    assert_eq!(game.state.players[1].cash, 300 + 8);
+}
+
+#[test]
+fn bid_hits_resting_offer() {
+    let mut game = trading_game(&[[3, 3, 2, 2]; 4]);
+    game.place_quote(quote(2, Suit::Spade, Side::Offer, 11)).unwrap();
+    let events = game.place_quote(quote(3, Suit::Spade, Side::Bid, 11)).unwrap();
+
+    assert!(matches!(&events[..], [Event::TradeExecuted { buyer, seller, .. }]
+        if *buyer == player_id(3) && *seller == player_id(2)));
+    assert_eq!(cards(&game, 2, Suit::Spade), 2);
+    assert_eq!(cards(&game, 3, Suit::Spade), 4);
+}
+
+#[test]
+fn player_does_not_trade_with_self() {
+    let mut game = trading_game(&[[3, 3, 2, 2]; 4]);
+    game.place_quote(quote(0, Suit::Heart, Side::Bid, 6)).unwrap();
+    let events = game.place_quote(quote(0, Suit::Heart, Side::Offer, 6)).unwrap();
+
+    assert!(matches!(&events[..], [Event::QuotePlaced { .. }]));
+    assert_eq!(game.state.quotes.len(), 2);
+    assert_eq!(cards(&game, 0, Suit::Heart), 3);
+}
+
+#[test]
+fn stale_offer_does_not_execute() {
+    let mut game = trading_game(&[[1, 3, 3, 3], [3, 3, 2, 2], [3, 3, 2, 2], [3, 3, 2, 2]]);
+    game.place_quote(quote(0, Suit::Spade, Side::Offer, 10)).unwrap();
+    // 挂单之后卖家手里的牌没了
+    game.state.players[0].hand.cards.insert(Suit::Spade, 0);
+
+    let err = game.place_quote(quote(1, Suit::Spade, Side::Bid, 10)).unwrap_err();
+    assert_eq!(err, FiggieError::InsufficientCards { player_id: player_id(0), suit: Suit::Spade });
+    assert_eq!(game.state.quotes.len(), 1);
+    assert_eq!(game.state.players[1].cash, 300);
+}
+
+#[test]
+fn cancel_quote_removes_exact_match() {
+    let mut game = trading_game(&[[3, 3, 2, 2]; 4]);
+    let q = quote(0, Suit::Club, Side::Bid, 5);
+    game.place_quote(q.clone()).unwrap();
+
+    let mut other_price = q.clone();
+    other_price.price = 6;
+    assert_eq!(game.cancel_quote(other_price).unwrap_err(), FiggieError::QuoteNotFound);
+
+    let mut other_player = q.clone();
+    other_player.player_id = player_id(1);
+    assert_eq!(game.cancel_quote(other_player).unwrap_err(), FiggieError::QuoteNotFound);
+
+    let events = game.cancel_quote(q).unwrap();
+    assert!(matches!(&events[..], [Event::QuoteCanceled { .. }]));
+    assert!(game.state.quotes.is_empty());
+}
+
+#[test]
+fn start_round_deals_standard_deck() {
+    for n in [4, 5] {
+        let (mut game, clock) = new_game(config(n));
+        let events = game.start_round(1).unwrap();
+
+        assert_eq!(game.phase, Phase::Trading);
+        assert_eq!(events.len(), n);
+        for event in &events {
+            match event {
+                Event::RoundStarted { round_id, server_time, player } => {
+                    assert_eq!(*round_id, 1);
+                    assert_eq!(*server_time, clock.now().as_secs());
+                    assert_eq!(total_cards(&player.hand) as usize, 40 / n);
+                }
+                other => panic!("unexpected event: {other:?}"),
+            }
+        }
+
+        let dealt: u32 = game.state.players.iter().map(|p| total_cards(&p.hand)).sum();
+        assert_eq!(dealt, 40);
+
+        let mut counts: Vec<u8> = game.suit_counts.values().copied().collect();
+        counts.sort();
+        assert_eq!(counts, vec![8, 10, 10, 12]);
+        assert_eq!(game.suit_counts[&game.common_suit], 12);
+        assert_ne!(game.goal_suit, game.common_suit);
+        assert_eq!(is_red(game.goal_suit), is_red(game.common_suit));
+
+        // 每种花色的实际发牌数与公布的构成一致
+        for (suit, count) in &game.suit_counts {
+            let held: u8 = game.state.players.iter()
+                .map(|p| p.hand.cards.get(suit).copied().unwrap_or(0))
+                .sum();
+            assert_eq!(held, *count);
+        }
+
+        for player in &game.state.players {
+            assert_eq!(game.starting_hands[&player.info.id].cards, player.hand.cards);
+        }
+    }
+}
+
+#[test]
+fn start_round_handles_leftover_cards() {
+    let (mut discard, _) = new_game(config(3));
+    discard.start_round(1).unwrap();
+    assert!(discard.state.players.iter().all(|p| total_cards(&p.hand) == 13));
+    // 公布的构成只算发出的 39 张，与手牌一致
+    assert_eq!(discard.suit_counts.values().map(|c| *c as u32).sum::<u32>(), 39);
+    for (suit, count) in &discard.suit_counts {
+        let held: u8 = discard.state.players.iter().map(|p| p.hand.cards.get(suit).copied().unwrap_or(0)).sum();
+        assert_eq!(held, *count);
+    }
+
+    let mut extra_config = config(3);
+    extra_config.leftover_policy = LeftoverPolicy::DealExtra;
+    let (mut extra, _) = new_game(extra_config);
+    extra.start_round(1).unwrap();
+    let sizes: Vec<u32> = extra.state.players.iter().map(|p| total_cards(&p.hand)).collect();
+    assert_eq!(sizes, vec![14, 13, 13]);
+}
+
+#[test]
+fn start_round_clears_book_and_rejects_while_trading() {
+    let mut game = trading_game(&[[3, 3, 2, 2]; 4]);
+    game.place_quote(quote(0, Suit::Club, Side::Bid, 5)).unwrap();
+    assert_eq!(game.start_round(2).unwrap_err(), FiggieError::WrongPhase(Phase::Trading));
+
+    game.end_round().unwrap();
+    assert!(game.state.quotes.is_empty());
+    game.start_round(2).unwrap();
+    assert_eq!(game.round, 2);
+    assert!(game.state.quotes.is_empty());
+}
+
+#[test]
+fn end_round_pays_bonus_and_rest_of_pot() {
+    let mut game = trading_game(&[[2, 3, 5, 0], [3, 2, 3, 2], [3, 3, 2, 2], [2, 2, 0, 6]]);
+    game.goal_suit = Suit::Diamond;
+    game.end_round().unwrap();
+
+    // 10 张 goal 牌：bonus 共 100，剩余 100 归持有最多的玩家
+    let cash: Vec<i32> = game.state.players.iter().map(|p| p.cash).collect();
+    assert_eq!(cash, vec![300 + 50 + 100, 300 + 30, 300 + 20, 300]);
+}
+
+#[test]
+fn end_round_splits_pot_between_tied_winners() {
+    let mut game = trading_game(&[[2, 3, 4, 1], [2, 3, 4, 1], [3, 3, 0, 4], [3, 3, 0, 4]]);
+    game.goal_suit = Suit::Diamond;
+    game.end_round().unwrap();
+
+    // 8 张 goal 牌：bonus 共 80，剩余 120 两人平分
+    let cash: Vec<i32> = game.state.players.iter().map(|p| p.cash).collect();
+    assert_eq!(cash, vec![300 + 40 + 60, 300 + 40 + 60, 300, 300]);
+}
+
+#[test]
+fn end_round_three_way_tie_rounds_down() {
+    let mut game = trading_game(&[[3, 3, 3, 1], [3, 3, 3, 1], [3, 3, 3, 1], [1, 1, 1, 7]]);
+    game.goal_suit = Suit::Heart;
+    game.end_round().unwrap();
+
+    // 10 张 goal 牌，剩余 100 三人平分，每人 33
+    let cash: Vec<i32> = game.state.players.iter().map(|p| p.cash).collect();
+    assert_eq!(cash, vec![300 + 30 + 33, 300 + 30 + 33, 300 + 30 + 33, 300 + 10]);
+}
+
+#[test]
+fn end_round_without_goal_cards_has_no_winner() {
+    let mut game = trading_game(&[[5, 5, 0, 0]; 4]);
+    game.goal_suit = Suit::Diamond;
+    game.end_round().unwrap();
+    assert!(game.state.players.iter().all(|p| p.cash == 300));
+}
+
+#[test]
+fn end_round_reveals_deal() {
+    let (mut game, clock) = new_game(config(4));
+    game.start_round(1).unwrap();
+    let starting = game.starting_hands.clone();
+    clock.advance(Duration::from_secs(90));
+
+    let events = game.end_round().unwrap();
+    match &events[..] {
+        [Event::RoundEnded { server_time, goal_suit, common_suit, suit_counts, starting_hands, .. }] => {
+            assert_eq!(*server_time, clock.now().as_secs());
+            assert_eq!(*goal_suit, game.goal_suit);
+            assert_eq!(*common_suit, game.common_suit);
+            assert_eq!(suit_counts, &game.suit_counts);
+            assert_eq!(starting_hands.len(), 4);
+            for (id, hand) in starting_hands {
+                assert_eq!(hand.cards, starting[id].cards);
+            }
+        }
+        other => panic!("unexpected events: {other:?}"),
+    }
+}
+
+#[test]
+fn round_timer_follows_clock() {
+    let (mut game, clock) = new_game(config(4));
+    assert_eq!(game.round_time_remaining(), Duration::ZERO);
+
+    game.start_round(1).unwrap();
+    assert_eq!(game.round_time_remaining(), Duration::from_secs(240));
+
+    clock.advance(Duration::from_secs(100));
+    assert_eq!(game.round_elapsed(), Duration::from_secs(100));
+    assert_eq!(game.round_time_remaining(), Duration::from_secs(140));
+
+    clock.advance(Duration::from_secs(500));
+    assert_eq!(game.round_time_remaining(), Duration::ZERO);
+}
+
+#[test]
+fn end_game_only_once() {
+    let (mut game, _) = new_game(config(4));
+    let events = game.end_game().unwrap();
+    assert!(matches!(&events[..], [Event::GameEnded { players, completed: false }] if players.len() == 4));
+    assert_eq!(game.end_game().unwrap_err(), FiggieError::WrongPhase(Phase::GameOver));
+    assert_eq!(game.start_round(2).unwrap_err(), FiggieError::WrongPhase(Phase::GameOver));
+}
+
+// 只有交易时间走完的回合才计数，打满配置的回合数才算完整对局；回合编号不影响计数
+#[test]
+fn game_completes_after_configured_rounds() {
+    let mut config = config(4);
+    config.rounds = 2;
+    let full = Duration::from_secs(config.trading_duration_secs as u64);
+    let (mut game, clock) = new_game(config);
+    game.start_round(7).unwrap();
+    clock.advance(full);
+    game.end_round().unwrap();
+
+    // 提前结束的回合不算
+    game.start_round(8).unwrap();
+    clock.advance(full - Duration::from_secs(1));
+    game.end_round().unwrap();
+    assert_eq!(game.full_rounds, 1);
+    let mut early = Game::restore(game.snapshot(), clock.clone()).unwrap();
+    assert!(matches!(&early.end_game().unwrap()[..], [Event::GameEnded { completed: false, .. }]));
+
+    game.start_round(9).unwrap();
+    clock.advance(full);
+    game.end_round().unwrap();
+    assert_eq!(game.full_rounds, 2);
+    let mut restored = Game::restore(game.snapshot(), clock).unwrap();
+    assert!(matches!(&restored.end_game().unwrap()[..], [Event::GameEnded { completed: true, .. }]));
+}
+
+#[test]
+fn new_rejects_zero_rounds() {
+    let mut config = config(4);
+    config.rounds = 0;
+    assert_eq!(Game::new(config).unwrap_err(), FiggieError::InvalidRoundCount(0));
+}
+
+#[test]
+fn scripted_game_through_handle_action() {
+    let (mut game, clock) = new_game(config(4));
+    game.handle_action(Action::StartRound(1)).unwrap();
+    for (player, counts) in game.state.players.iter_mut().zip([[3, 3, 2, 2], [2, 2, 4, 2], [3, 3, 2, 2], [2, 2, 2, 4]]) {
+        player.hand = hand(counts);
+    }
+    game.goal_suit = Suit::Diamond;
+
+    // p0 买入 p1 的两张 Diamond
+    game.handle_action(Action::PlaceQuote(quote(0, Suit::Diamond, Side::Bid, 9))).unwrap();
+    game.handle_action(Action::PlaceQuote(quote(1, Suit::Diamond, Side::Offer, 9))).unwrap();
+    game.handle_action(Action::PlaceQuote(quote(1, Suit::Diamond, Side::Offer, 12))).unwrap();
+    game.handle_action(Action::PlaceQuote(quote(0, Suit::Diamond, Side::Bid, 12))).unwrap();
+
+    // p2 挂单后撤单
+    let resting = quote(2, Suit::Club, Side::Offer, 20);
+    game.handle_action(Action::PlaceQuote(resting.clone())).unwrap();
+    game.handle_action(Action::CancelQuote(resting)).unwrap();
+    assert!(game.state.quotes.is_empty());
+
+    clock.advance(Duration::from_secs(240));
+    let events = game.handle_action(Action::EndRound).unwrap();
+    assert!(matches!(&events[..], [Event::RoundEnded { round_id: 1, .. }]));
+
+    // Diamond：p0 4 张，p1 2 张，p2 2 张，p3 2 张
+    let cash: Vec<i32> = game.state.players.iter().map(|p| p.cash).collect();
+    assert_eq!(cash, vec![
+        300 - 21 + 40 + 100,
+        300 + 21 + 20,
+        300 + 20,
+        300 + 20,
+    ]);
+
+    let events = game.handle_action(Action::StartRound(2)).unwrap();
+    assert_eq!(events.len(), 4);
+    let events = game.handle_action(Action::EndGame).unwrap();
+    match &events[..] {
+        [Event::GameEnded { players, completed: false }] => {
+            let final_cash: Vec<i32> = players.iter().map(|p| p.cash).collect();
+            assert_eq!(final_cash, cash);
+        }
+        other => panic!("unexpected events: {other:?}"),
+    }
+    assert!(game.handle_action(Action::StartRound(3)).is_err());
+}
+
+#[test]
+fn cancel_player_quotes_only_touches_that_player() {
+    let mut game = trading_game(&[[3, 3, 2, 2]; 4]);
+    game.place_quote(quote(0, Suit::Club, Side::Bid, 5)).unwrap();
+    game.place_quote(quote(0, Suit::Heart, Side::Offer, 9)).unwrap();
+    game.place_quote(quote(1, Suit::Spade, Side::Bid, 4)).unwrap();
+
+    let events = game.cancel_player_quotes(&player_id(0));
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|e| matches!(e, Event::QuoteCanceled { quote } if quote.player_id == player_id(0))));
+    assert_eq!(game.state.quotes.len(), 1);
+    assert!(game.cancel_player_quotes(&player_id(0)).is_empty());
+}
+
+#[test]
+fn snapshot_restores_state_and_round_timer() {
+    let (mut game, clock) = new_game(config(4));
+    game.start_round(1).unwrap();
+    game.place_quote(quote(0, Suit::Club, Side::Bid, 1)).unwrap();
+    clock.advance(Duration::from_secs(100));
+
+    let snapshot = game.snapshot();
+    let text = serde_json::to_string(&snapshot).unwrap();
+
+    // 在另一个时间点恢复，剩余时间不变
+    let later = Arc::new(ManualClock::new(START + Duration::from_secs(5_000)));
+    let restored = Game::restore(serde_json::from_str(&text).unwrap(), later).unwrap();
+    assert_eq!(restored.phase, Phase::Trading);
+    assert_eq!(restored.goal_suit, game.goal_suit);
+    assert_eq!(serde_json::to_value(&restored.state).unwrap(), serde_json::to_value(&game.state).unwrap());
+    assert_eq!(restored.starting_hands.len(), 4);
+    assert_eq!(restored.round_time_remaining(), Duration::from_secs(140));
+
+    let mut broken = game.snapshot();
+    broken.state.players.pop();
+    assert!(matches!(Game::restore(broken, clock), Err(FiggieError::Internal(_))));
+}
+
+#[test]
+fn chat_checks_players_and_targets_recipient() {
+    let (mut game, _) = new_game(config(4));
+    let events = game.handle_action(Action::Chat { player_id: player_id(0), to: None, text: "hi".to_string() }).unwrap();
+    assert!(matches!(&events[0], Event::ChatMessage { from, to: None, .. } if *from == player_id(0)));
+    assert!(events[0].target_player().is_none());
+
+    let events = game.chat(player_id(0), Some(player_id(2)), "psst".to_string()).unwrap();
+    assert_eq!(events[0].target_player(), Some(&player_id(2)));
+    assert_eq!(events[0].copy_to(), Some(&player_id(0)));
+
+    assert_eq!(game.chat("ghost".to_string(), None, "boo".to_string()).unwrap_err(), FiggieError::UnknownPlayer("ghost".to_string()));
+    assert_eq!(game.chat(player_id(0), Some("ghost".to_string()), "x".to_string()).unwrap_err(), FiggieError::UnknownPlayer("ghost".to_string()));
+    assert_eq!(game.chat(player_id(0), None, "  ".to_string()).unwrap_err(), FiggieError::EmptyMessage);
+}
+
+#[test]
+fn pause_freezes_timer_and_rejects_quotes() {
+    let (mut game, clock) = new_game(config(4));
+    assert_eq!(game.handle_action(Action::Pause).unwrap_err(), FiggieError::WrongPhase(Phase::Waiting));
+
+    game.start_round(1).unwrap();
+    game.place_quote(quote(0, Suit::Club, Side::Bid, 1)).unwrap();
+    clock.advance(Duration::from_secs(40));
+    let events = game.handle_action(Action::Pause).unwrap();
+    assert!(matches!(&events[..], [Event::TradingPaused { remaining_millis: 200_000, .. }]));
+    assert!(game.pause().unwrap().is_empty());
+
+    // 暂停期间计时不走，新挂单被拒绝，撤单照常
+    clock.advance(Duration::from_secs(300));
+    assert_eq!(game.round_time_remaining(), Duration::from_secs(200));
+    assert_eq!(game.place_quote(quote(1, Suit::Club, Side::Bid, 2)).unwrap_err(), FiggieError::Paused);
+    assert!(game.cancel_quote(quote(0, Suit::Club, Side::Bid, 1)).is_ok());
+
+    let snapshot = game.snapshot();
+    assert!(snapshot.paused);
+    let later = Arc::new(ManualClock::new(START + Duration::from_secs(9_000)));
+    let restored = Game::restore(snapshot, later).unwrap();
+    assert!(restored.is_paused());
+    assert_eq!(restored.round_time_remaining(), Duration::from_secs(200));
+
+    let events = game.handle_action(Action::Resume).unwrap();
+    assert!(matches!(&events[..], [Event::TradingResumed { remaining_millis: 200_000, .. }]));
+    assert!(game.resume().unwrap().is_empty());
+    clock.advance(Duration::from_secs(50));
+    assert_eq!(game.round_time_remaining(), Duration::from_secs(150));
+    assert!(game.place_quote(quote(1, Suit::Club, Side::Bid, 2)).is_ok());
+
+    game.pause().unwrap();
+    assert_eq!(game.handle_action(Action::EndRound).unwrap_err(), FiggieError::Paused);
+    game.resume().unwrap();
+    game.end_round().unwrap();
+    assert!(!game.is_paused());
+}
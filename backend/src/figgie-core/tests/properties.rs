@@ -0,0 +1,150 @@
+mod common;
+
+use common::*;
+use figgie_core::*;
+
+use proptest::prelude::*;
+
+const SUITS: [Suit; 4] = [Suit::Spade, Suit::Heart, Suit::Diamond, Suit::Club];
+
+fn suit_strategy() -> impl Strategy<Value = Suit> {
+    prop::sample::select(SUITS.to_vec())
+}
+
+fn side_strategy() -> impl Strategy<Value = Side> {
+    prop_oneof![Just(Side::Bid), Just(Side::Offer)]
+}
+
+// 价格集中在小范围内，保证能经常撮合；偶尔出现非法价格
+fn action_strategy(num_players: usize) -> impl Strategy<Value = (bool, usize, Suit, Side, u32)> {
+    (
+        prop::bool::weighted(0.85),
+        0..num_players,
+        suit_strategy(),
+        side_strategy(),
+        prop_oneof![8 => 1u32..12, 1 => Just(0u32), 1 => Just(101u32)],
+    )
+}
+
+fn policy_strategy() -> impl Strategy<Value = LeftoverPolicy> {
+    prop_oneof![Just(LeftoverPolicy::Discard), Just(LeftoverPolicy::DealExtra)]
+}
+
+proptest! {
+    #[test]
+    fn trading_conserves_cash_and_cards(
+        num_players in 4usize..=5,
+        actions in prop::collection::vec(action_strategy(5), 0..200),
+    ) {
+        let (mut game, _) = new_game(config(num_players));
+        game.start_round(1).unwrap();
+
+        let cash_before: i32 = game.state.players.iter().map(|p| p.cash).sum();
+        let suit_total = |game: &Game, suit: Suit| -> i32 {
+            game.state.players.iter().map(|p| p.hand.cards.get(&suit).copied().unwrap_or(0) as i32).sum()
+        };
+        let cards_before: Vec<i32> = SUITS.iter().map(|s| suit_total(&game, *s)).collect();
+
+        for (place, player, suit, side, price) in actions {
+            let q = quote(player % num_players, suit, side, price);
+            let result = if place {
+                game.handle_action(Action::PlaceQuote(q))
+            } else {
+                game.handle_action(Action::CancelQuote(q))
+            };
+
+            // 每次成交都是一张牌换等额现金
+            if let Ok(events) = result {
+                for event in events {
+                    if let Event::TradeExecuted { buyer, seller, .. } = event {
+                        prop_assert_ne!(buyer, seller);
+                    }
+                }
+            }
+
+            // 同一花色同一方向最多一张挂单，且挂单都在合法价格范围内
+            for q in &game.state.quotes {
+                prop_assert!((1..=100).contains(&q.price));
+                let same = game.state.quotes.iter().filter(|o| o.suit == q.suit && o.side == q.side).count();
+                prop_assert_eq!(same, 1);
+            }
+        }
+
+        let cash_after: i32 = game.state.players.iter().map(|p| p.cash).sum();
+        prop_assert_eq!(cash_before, cash_after);
+        let cards_after: Vec<i32> = SUITS.iter().map(|s| suit_total(&game, *s)).collect();
+        prop_assert_eq!(cards_before, cards_after);
+        for player in &game.state.players {
+            prop_assert!(player.hand.cards.values().all(|&c| c <= 12));
+        }
+    }
+
+    #[test]
+    fn deal_invariants_hold_for_all_variants(
+        num_players in MIN_PLAYERS..=MAX_PLAYERS,
+        policy in policy_strategy(),
+        rounds in 1u32..4,
+    ) {
+        let mut cfg = config(num_players);
+        cfg.leftover_policy = policy;
+        let (mut game, _) = new_game(cfg);
+
+        for round in 1..=rounds {
+            game.start_round(round).unwrap();
+
+            let deck_size = DeckConfig::default().size();
+            let sizes: Vec<usize> = game.state.players.iter().map(|p| total_cards(&p.hand) as usize).collect();
+            let dealt: usize = sizes.iter().sum();
+            let expected = match policy {
+                LeftoverPolicy::Discard => deck_size - deck_size % num_players,
+                LeftoverPolicy::DealExtra => deck_size,
+            };
+            prop_assert_eq!(dealt, expected);
+            let (min, max) = (sizes.iter().min().unwrap(), sizes.iter().max().unwrap());
+            prop_assert!(max - min <= 1);
+
+            // 公布的构成与发出的手牌一致；余牌全部发出时 common suit 正好 12 张
+            for (suit, count) in &game.suit_counts {
+                let held: u8 = game.state.players.iter().map(|p| p.hand.cards.get(suit).copied().unwrap_or(0)).sum();
+                prop_assert_eq!(held, *count);
+            }
+            if dealt == deck_size {
+                prop_assert_eq!(game.suit_counts[&game.common_suit], 12);
+            }
+            prop_assert_ne!(game.goal_suit, game.common_suit);
+            prop_assert_eq!(is_red(game.goal_suit), is_red(game.common_suit));
+
+            game.end_round().unwrap();
+        }
+    }
+
+    #[test]
+    fn settlement_never_exceeds_pot(
+        hands in prop::collection::vec(prop::array::uniform4(0u8..=6), 4..=5),
+        goal in suit_strategy(),
+    ) {
+        let mut game = trading_game(&hands);
+        game.goal_suit = goal;
+        let before: Vec<i32> = game.state.players.iter().map(|p| p.cash).collect();
+        game.end_round().unwrap();
+
+        let goal_counts: Vec<i32> = game.state.players.iter()
+            .map(|p| p.hand.cards.get(&goal).copied().unwrap_or(0) as i32)
+            .collect();
+        let max_goal = *goal_counts.iter().max().unwrap();
+        let total_bonus: i32 = goal_counts.iter().sum::<i32>() * CARD_VALUE_PER_GOAL_SUIT;
+        let winners = goal_counts.iter().filter(|&&c| max_goal > 0 && c == max_goal).count() as i32;
+        let rest = (200 - total_bonus).max(0);
+
+        for (i, player) in game.state.players.iter().enumerate() {
+            let mut expected = before[i] + goal_counts[i] * CARD_VALUE_PER_GOAL_SUIT;
+            if max_goal > 0 && goal_counts[i] == max_goal {
+                expected += rest / winners;
+            }
+            prop_assert_eq!(player.cash, expected);
+        }
+
+        let paid: i32 = game.state.players.iter().zip(&before).map(|(p, b)| p.cash - b).sum();
+        prop_assert!(paid <= total_bonus.max(200));
+    }
+}
@@ -0,0 +1,23 @@
+use std::process::Command;
+
+// 编译时记录 git 提交，/version 中返回；不在 git 仓库中构建时为 unknown
+fn main() {
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=FIGGIE_GIT_HASH={hash}");
+    println!("cargo:rerun-if-env-changed=FIGGIE_GIT_HASH");
+    if let Ok(out) = Command::new("git").args(["rev-parse", "--git-dir"]).output()
+        && out.status.success()
+    {
+        let git_dir = String::from_utf8_lossy(&out.stdout).trim().to_string();
+        println!("cargo:rerun-if-changed={git_dir}/HEAD");
+        println!("cargo:rerun-if-changed={git_dir}/refs");
+    }
+}
@@ -1,115 +1,130 @@
-use serde_json::{json, Map, Value};
-use figgie_core::{Hand, Event};
+use crate::protocol::{ClientMessage, ServerMessage};
+use figgie_core::{Event, Hand};
+use serde::Deserialize;
+use serde_json::Value;
 
-pub fn hand_to_json(hand: &Hand) -> Value {
-    let mut obj = Map::new();
+// 连接使用的编码，通过 WebSocket 子协议或 ?format= 协商，默认 JSON
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WireFormat {
+    #[default]
+    Json,
+    // MessagePack，走二进制帧
+    Msgpack,
+}
 
-    for (suit, count) in &hand.cards {
-        obj.insert(suit.to_string(), json!(count));
+impl WireFormat {
+    // 按优先级排列，握手时服务端选第一个客户端也支持的
+    pub const SUBPROTOCOLS: [&'static str; 2] = ["figgie.msgpack.v1", "figgie.json.v1"];
+
+    pub fn from_subprotocol(protocol: &str) -> Option<Self> {
+        match protocol {
+            "figgie.msgpack.v1" => Some(WireFormat::Msgpack),
+            "figgie.json.v1" => Some(WireFormat::Json),
+            _ => None,
+        }
     }
+}
 
-    Value::Object(obj)
+// 消息的 JSON 形式由 serde 定义生成（事件来自 figgie-core），与协议 schema 保持一致
+pub fn server_message_to_text(message: &ServerMessage) -> Result<String, serde_json::Error> {
+    serde_json::to_string(message)
 }
 
+// 单个事件的 {"type": ..., "payload": {...}}，与消息信封中的内容相同
+#[allow(dead_code)]
 pub fn event_to_json(event: &Event) -> Value {
-    match event {
-        Event::TradeExecuted {
-            buyer,
-            seller,
-            suit,
-            price,
-        } => json!({
-            "type": "TradeExecuted",
-            "payload": {
-                "buyer": buyer.to_string(),
-                "seller": seller.to_string(),
-                "suit": suit.to_string(),
-                "price": price
-            }
-        }),
+    serde_json::to_value(event).unwrap_or(Value::Null)
+}
+
+// 手牌按花色展开为 {"Spade": 3, ...}
+#[allow(dead_code)]
+pub fn hand_to_json(hand: &Hand) -> Value {
+    serde_json::to_value(hand).unwrap_or(Value::Null)
+}
 
-        Event::QuoteCanceled{quote} => json!({
-            "type": "QuoteCanceled",
-            "payload": {
-                "quote": {
-                    "player_id": quote.player_id.to_string(),
-                    "suit": quote.suit.to_string(),
-                    "side": quote.side.to_string(),
-                    "price": quote.price,
-                }
-            }
-        }),
+// MessagePack 与 JSON 结构相同，带字段名，两个方向都一样
+pub fn server_message_to_msgpack(message: &ServerMessage) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+    rmp_serde::to_vec_named(message)
+}
+
+pub fn client_message_from_msgpack(bytes: &[u8]) -> Result<ClientMessage, rmp_serde::decode::Error> {
+    rmp_serde::from_slice(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ActionView;
+    use figgie_core::{Info, Player, Quote, Side, Suit};
+    use std::collections::HashMap;
 
-        Event::QuotePlaced {quote } => json!({
-            "type": "QuotePlaced",
-            "payload": {
-                "quote": {
-                    "player_id": quote.player_id.to_string(),
-                    "suit": quote.suit.to_string(),
-                    "side": quote.side.to_string(),
-                    "price": quote.price
-                }
-            }
-        }),
+    #[test]
+    fn msgpack_matches_the_json_structure() {
+        let event = Event::QuotePlaced {
+            quote: Quote {
+                player_id: "p1".to_string(),
+                suit: Suit::Diamond,
+                side: Side::Offer,
+                price: 12,
+            },
+        };
+        let message = ServerMessage::from(event.clone());
+        let binary = server_message_to_msgpack(&message).unwrap();
+        let text = server_message_to_text(&message).unwrap();
+        assert!(binary.len() < text.len());
 
-        Event::RoundStarted {
-            round_id,
-            server_time,
-            player,
-        } => json!({
-            "type": "RoundStarted",
-            "payload": {
-                "round_id": round_id,
-                "server_time": server_time,
-                "player": {
-                    "info": {
-                        "id": player.info.id.to_string(),
-                        "name": player.info.name.to_string()
-                    },
-                    "cash": player.cash,
-                    "hand": hand_to_json(&player.hand)
-                }
-            }
-        }),
+        let from_binary: Value = rmp_serde::from_slice(&binary).unwrap();
+        let from_text: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(from_binary, from_text);
+        assert_eq!(from_binary["payload"], event_to_json(&event)["payload"]);
+        assert_eq!(from_binary["payload"]["quote"]["side"], "Offer");
+    }
+
+    #[test]
+    fn msgpack_is_smaller_than_json() {
+        let hand = Hand { cards: [(Suit::Spade, 3), (Suit::Heart, 2), (Suit::Diamond, 3), (Suit::Club, 2)].into() };
+        let players: Vec<Player> = (0..5)
+            .map(|i| Player {
+                info: Info { id: format!("p{i}"), name: format!("Player {i}") },
+                cash: 310,
+                hand: hand.clone(),
+            })
+            .collect();
+        let event = Event::RoundEnded {
+            round_id: 1,
+            players: players.clone(),
+            server_time: 1_700_000_000,
+            goal_suit: Suit::Heart,
+            common_suit: Suit::Diamond,
+            suit_counts: HashMap::from([(Suit::Spade, 10), (Suit::Heart, 8), (Suit::Diamond, 12), (Suit::Club, 10)]),
+            starting_hands: players.iter().map(|p| (p.info.id.clone(), hand.clone())).collect(),
+        };
+        let message = ServerMessage::from(event.clone());
+        let json = server_message_to_text(&message).unwrap().len();
+        let binary = server_message_to_msgpack(&message).unwrap().len();
+        assert!(binary < json, "msgpack {binary} vs json {json}");
 
-        Event::RoundEnded {
-            round_id,
-            players,
-            server_time,
-            goal_suit,
-        } => json!({
-            "type": "RoundEnded",
-            "payload": {
-                "round_id": round_id,
-                "server_time": server_time,
-                "goal_suit": goal_suit.to_string(),
-                "players": players.iter().map(|player| {
-                    json!({
-                        "info": {
-                            "id": player.info.id.to_string(),
-                            "name": player.info.name.to_string()
-                        },
-                        "cash": player.cash,
-                        "hand": hand_to_json(&player.hand)
-                    })
-                }).collect::<Vec<_>>()
-            }
-        }),
+        let payload = &event_to_json(&event)["payload"];
+        assert_eq!(payload["starting_hands"]["p0"], hand_to_json(&hand));
+        assert_eq!(hand_to_json(&hand)["Diamond"], 3);
+    }
 
-        Event::GameEnded { players } => json!({
-            "type": "GameEnded",
-            "payload": {
-                "players": players.iter().map(|player| {
-                    json!({
-                        "info": {
-                            "id": player.info.id.to_string(),
-                            "name": player.info.name.to_string()
-                        },
-                        "cash": player.cash,
-                        "hand": hand_to_json(&player.hand)
-                    })
-                }).collect::<Vec<_>>()
-            }
-        }),
+    #[test]
+    fn decodes_client_actions_from_msgpack() {
+        let message = ClientMessage {
+            v: Some(1),
+            id: Some("9".to_string()),
+            action: ActionView::PlaceQuote {
+                player_id: "p1".to_string(),
+                suit: Suit::Heart,
+                side: Side::Bid,
+                price: 7,
+            },
+        };
+        let bytes = rmp_serde::to_vec_named(&message).unwrap();
+        let decoded = client_message_from_msgpack(&bytes).unwrap();
+        assert_eq!(decoded.id.as_deref(), Some("9"));
+        assert!(matches!(decoded.action, ActionView::PlaceQuote { suit: Suit::Heart, price: 7, .. }));
     }
-}
\ No newline at end of file
+}
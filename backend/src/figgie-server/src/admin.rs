@@ -0,0 +1,263 @@
+use crate::app::AppState;
+use crate::auth::AuthUser;
+use crate::dispatcher::*;
+use crate::robots::robot_loop;
+
+use axum::{
+    extract::{FromRequestParts, Path, State},
+    http::{request::Parts, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use figgie_core::Action;
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+use tokio::time::{timeout, Duration};
+
+// 等待房间响应管理命令的最长时间
+const ROOM_TIMEOUT: Duration = Duration::from_secs(5);
+
+type ApiError = (StatusCode, Json<Value>);
+
+fn error(status: StatusCode, message: impl ToString) -> ApiError {
+    (status, Json(json!({ "error": message.to_string() })))
+}
+
+fn rejected(e: impl Into<ActionError>) -> ApiError {
+    let e = e.into();
+    (StatusCode::CONFLICT, Json(json!({ "error": e.to_string(), "code": e.code() })))
+}
+
+// 已登录且在 admin.players 名单中的账号
+pub struct AdminUser(pub String);
+
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let AuthUser(player_id) = AuthUser::from_request_parts(parts, state).await?;
+        if state.config.admin.players.contains(&player_id) {
+            Ok(AdminUser(player_id))
+        } else {
+            Err(error(StatusCode::FORBIDDEN, "admin only"))
+        }
+    }
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/rooms", get(list_rooms))
+        .route("/rooms/{room_id}", get(dump_room))
+        .route("/rooms/{room_id}/end-round", post(end_round))
+        .route("/rooms/{room_id}/end-game", post(end_game))
+        .route("/rooms/{room_id}/pause", post(pause))
+        .route("/rooms/{room_id}/resume", post(resume))
+        .route("/rooms/{room_id}/players/{player_id}/kick", post(kick))
+        .route("/rooms/{room_id}/players/{player_id}/robot", post(replace_with_robot))
+}
+
+async fn find_room(state: &AppState, room_id: &str) -> Result<RoomHandle, ApiError> {
+    state
+        .dispatchers
+        .lock()
+        .await
+        .iter()
+        .find(|d| d.room_id == room_id)
+        .cloned()
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "room not found"))
+}
+
+// 发送一条带回复的命令并等待房间响应
+async fn ask<T>(room: &RoomHandle, command: impl FnOnce(oneshot::Sender<T>) -> Command) -> Result<T, ApiError> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    let closed = || error(StatusCode::GONE, "room is no longer running");
+    room.commands.send(command(reply_tx)).await.map_err(|_| closed())?;
+    match timeout(ROOM_TIMEOUT, reply_rx).await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(_)) => Err(closed()),
+        Err(_) => Err(error(StatusCode::GATEWAY_TIMEOUT, "room did not respond")),
+    }
+}
+
+async fn act(room: &RoomHandle, action: Action) -> Result<(), ApiError> {
+    ask(room, |reply| Command::Action(ActionRequest { action, reply: Some(reply), by_admin: true }))
+        .await?
+        .map_err(rejected)
+}
+
+async fn list_rooms(State(state): State<AppState>, AdminUser(_): AdminUser) -> impl IntoResponse {
+    let rooms: Vec<RoomHandle> = state.dispatchers.lock().await.clone();
+    let mut infos = vec![];
+    for room in rooms {
+        // 刚结束的房间可能已经不响应，跳过即可
+        if let Ok(info) = ask(&room, Command::Inspect).await {
+            infos.push(info);
+        }
+    }
+    Json(infos)
+}
+
+async fn dump_room(
+    State(state): State<AppState>,
+    AdminUser(_): AdminUser,
+    Path(room_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let room = find_room(&state, &room_id).await?;
+    let info = ask(&room, Command::Inspect).await?;
+    let game = ask(&room, Command::Dump).await?;
+    Ok(Json(json!({ "room": info, "game": game })))
+}
+
+async fn end_round(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    Path(room_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let room = find_room(&state, &room_id).await?;
+    act(&room, Action::EndRound).await?;
+    log::info!("admin {} ended the round, room id: {}", admin, room_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn end_game(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    Path(room_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let room = find_room(&state, &room_id).await?;
+    act(&room, Action::EndGame).await?;
+    log::info!("admin {} ended the game, room id: {}", admin, room_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn pause(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    Path(room_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let room = find_room(&state, &room_id).await?;
+    act(&room, Action::Pause).await?;
+    log::info!("admin {} paused trading, room id: {}", admin, room_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn resume(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    Path(room_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let room = find_room(&state, &room_id).await?;
+    act(&room, Action::Resume).await?;
+    log::info!("admin {} resumed trading, room id: {}", admin, room_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 给座位换一个新的出站队列，返回新的参与者；原来的连接会被断开
+async fn reseat(state: &AppState, room_id: &str, player_id: &str, robot: bool) -> Result<(Participant, Seat), ApiError> {
+    let room = find_room(state, room_id).await?;
+    let (participant, outbox) = create_participant(
+        player_id.to_string(), room.room.clone(), room.commands.clone(), state.config.rooms.outbox_capacity,
+    );
+    let player_id = player_id.to_string();
+    let seat = ask(&room, |reply| Command::Reseat { player_id, outbox, robot, reply })
+        .await?
+        .map_err(rejected)?;
+    Ok((participant, seat))
+}
+
+async fn kick(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    Path((room_id, player_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    // 新的参与者直接丢弃，被踢的玩家无法再接入这个座位
+    reseat(&state, &room_id, &player_id, false).await?;
+    log::info!("admin {} kicked {}, room id: {}", admin, player_id, room_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn replace_with_robot(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    Path((room_id, player_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (participant, seat) = reseat(&state, &room_id, &player_id, true).await?;
+    let params = state.config.robots;
+    tokio::spawn(async move {
+        robot_loop(participant, seat.player.hand, seat.player.cash, seat.clock, params).await;
+    });
+    log::info!("admin {} handed {} to a robot, room id: {}", admin, player_id, room_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::game;
+    use figgie_core::*;
+    use std::sync::Arc;
+    use tokio::sync::mpsc::channel;
+
+    #[tokio::test]
+    async fn inspects_pauses_and_reseats() {
+        let game = game("R1");
+        let room = Arc::new(game.game_config.clone());
+        let (tx, rx) = channel(16);
+        let mut dispatcher = Dispatcher::new("R1".to_string(), game, rx);
+        let mut seats = vec![];
+        for info in room.players.iter() {
+            let (participant, outbox) = create_participant(info.id.clone(), room.clone(), tx.clone(), 16);
+            dispatcher.register(info.id.clone(), outbox);
+            seats.push(participant);
+        }
+        tokio::spawn(async move { dispatcher.run().await });
+        let handle = RoomHandle { room_id: "R1".to_string(), room: room.clone(), commands: tx.clone() };
+
+        tx.send(Command::Connected("p0".to_string())).await.unwrap();
+        let info = ask(&handle, Command::Inspect).await.unwrap();
+        assert_eq!((info.players, info.round, info.paused), (4, 1, false));
+        assert_eq!(info.connected, vec!["p0"]);
+        assert_eq!(ask(&handle, Command::Dump).await.unwrap().state.players.len(), 4);
+
+        // 暂停期间拒绝新挂单，撤单不受影响
+        act(&handle, Action::Pause).await.unwrap();
+        assert!(ask(&handle, Command::Inspect).await.unwrap().paused);
+        let quote = Quote { player_id: "p0".to_string(), suit: Suit::Club, side: Side::Bid, price: 5 };
+        let err = act(&handle, Action::PlaceQuote(quote.clone())).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+        assert!(act(&handle, Action::CancelQuote(quote.clone())).await.is_err_and(|e| e.1.0["code"] == "QuoteNotFound"));
+        act(&handle, Action::Resume).await.unwrap();
+        act(&handle, Action::PlaceQuote(quote)).await.unwrap();
+
+        // 踢出后原来的出站队列被关闭
+        let (_, outbox) = create_participant("p0".to_string(), room.clone(), tx.clone(), 16);
+        let seat = ask(&handle, |reply| Command::Reseat { player_id: "p0".to_string(), outbox, robot: false, reply })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(seat.player.info.id, "p0");
+        assert!(seats[0].event_receiver.is_closed());
+        let info = ask(&handle, Command::Inspect).await.unwrap();
+        assert!(info.connected.is_empty());
+        assert_eq!(info.kicked, vec!["p0"]);
+
+        let (_, outbox) = create_participant("p1".to_string(), room.clone(), tx.clone(), 16);
+        ask(&handle, |reply| Command::Reseat { player_id: "p1".to_string(), outbox, robot: true, reply })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ask(&handle, Command::Inspect).await.unwrap().robots, vec!["p1"]);
+
+        // 管理员提前结束的对局仍算打满
+        act(&handle, Action::EndGame).await.unwrap();
+        let mut ended = None;
+        while let Some(event) = seats[2].event_receiver.recv().await {
+            if let Event::GameEnded { completed, .. } = event {
+                ended = Some(completed);
+                break;
+            }
+        }
+        assert_eq!(ended, Some(true));
+    }
+}
@@ -2,178 +2,681 @@ use crate::types::*;
 use crate::dispatcher::*;
 use crate::robots::*;
 use crate::adapter::*;
+use crate::protocol::*;
+use crate::config::ServerConfig;
+use crate::snapshot;
+use crate::store::{LeaderboardQuery, Record, Recorder, Store};
+use crate::rating::{Ladder, Period};
+use crate::chat::ChatPolicy;
+use crate::matchmaking::{self, Matchmaker};
+use crate::admin;
+use crate::metrics::{self, ConnectionGuard};
+use crate::health;
+use crate::frontend;
+use crate::cors;
+use crate::limits::{self, FloodGuard, FloodLimits, Violation};
+use crate::auth::{self, AuthUser, Sessions};
 use figgie_core::*;
 
 use futures::{StreamExt, SinkExt};
+use tokio::select;
 use tokio::sync::mpsc::*;
-use tokio::sync::Mutex;
-use std::collections::HashMap;
-use std::time::Instant;
+use tokio::sync::{oneshot, Mutex};
+use tokio::time::{interval, Duration, Instant};
 use std::sync::Arc;
-use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde::Deserialize;
 use serde_json::json;
-use log;
 use axum::{
     routing::{get, post},
     response::IntoResponse,
     http::StatusCode,
-    extract::{Path, State},
-    extract::ws::{WebSocketUpgrade, Message},
+    extract::{Path, Query, State},
+    extract::ws::{close_code, CloseFrame, WebSocketUpgrade, Message},
     Router, Json
 };
 
+// 停机时等待每个房间响应的最长时间
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Clone)]
 pub struct AppState {
     pub dispatchers: Dispatchers,
     pub human_participants: HumanParticipants,
+    pub config: Arc<ServerConfig>,
+    // 收到停机信号后不再接受新房间
+    pub shutting_down: Arc<AtomicBool>,
+    pub store: Store,
+    // 后台写库线程的入口
+    pub records: UnboundedSender<Record>,
+    pub sessions: Arc<Sessions>,
+    pub matchmaker: Arc<Mutex<Matchmaker>>,
 }
 
-pub fn create_app() -> Router {
-    let dispatchers: Dispatchers = Arc::new(Mutex::new(Vec::new()));
-    let human_participants: HumanParticipants = Arc::new(Mutex::new(Vec::new()));
+impl AppState {
+    pub fn new(config: ServerConfig, store: Store, sessions: Sessions) -> Self {
+        AppState {
+            sessions: Arc::new(sessions),
+            matchmaker: Arc::new(Mutex::new(Matchmaker::default())),
+            records: store.spawn_writer(),
+            store,
+            dispatchers: Arc::new(Mutex::new(Vec::new())),
+            human_participants: Arc::new(Mutex::new(Vec::new())),
+            config: Arc::new(config),
+            shutting_down: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
 
-    Router::new()
+pub fn create_app(state: AppState) -> Router {
+    let static_dir = state.config.server.static_dir.clone();
+    let api = Router::new()
         .route("/start", post(start_game))
         .route("/ws/{room_id}/{player_id}", get(ws_connect))
-        .with_state(AppState {
-            dispatchers: dispatchers,
-            human_participants: human_participants,
-        })
-}
+        .route("/protocol/schema", get(protocol_schema))
+        .route("/games/{game_id}", get(game_record))
+        .route("/players/{player_id}/history", get(player_history))
+        .route("/players/{player_id}", get(auth::profile))
+        .route("/leaderboard", get(leaderboard))
+        .route("/metrics", get(metrics::metrics))
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz))
+        .route("/version", get(health::version))
+        .route(
+            "/matchmaking/queue",
+            get(matchmaking::status).post(matchmaking::enqueue).delete(matchmaking::leave),
+        )
+        .nest("/admin", admin::routes())
+        .route("/auth/register", post(auth::register))
+        .route("/auth/login", post(auth::login))
+        .route("/me", get(auth::me).put(auth::update_me))
+        .with_state(state.clone());
+    let api = match cors::layer(&state.config.server.cors_origins) {
+        Some(cors) => api.layer(cors),
+        None => api,
+    };
 
-async fn start_game(State(state): State<AppState>, Json(req): Json<StartGameRequest>,) -> impl IntoResponse {
-    let dispatchers = &state.dispatchers;
-    let human_participants = &state.human_participants;
+    match static_dir {
+        // 托管前端时 API 同时挂在 /api 下，与前端开发代理的路径一致
+        Some(dir) => {
+            frontend::check(&dir);
+            Router::new()
+                .nest("/api", api.clone())
+                .merge(api)
+                .fallback_service(frontend::service(&dir))
+        }
+        None => api,
+    }
+}
 
-    let player_num = req.players.len();
-    if player_num != 4 && player_num != 5 {
+async fn start_game(
+    State(state): State<AppState>,
+    AuthUser(caller): AuthUser,
+    Json(mut req): Json<StartGameRequest>,
+) -> impl IntoResponse {
+    // 开房的人必须坐在桌上；真人座位必须是已注册的账号，名字取自账号资料
+    if !req.players.iter().any(|p| p.id == caller) {
         return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": "Figgie requires 5 players" })),
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "caller must be one of the players" })),
         );
     }
-    println!("Game Started...");
+    let store = state.store.clone();
+    let ids: Vec<String> = req.players.iter().map(|p| p.id.clone()).collect();
+    let profiles = tokio::task::spawn_blocking(move || {
+        ids.into_iter()
+            .filter(|id| !id.starts_with("robot"))
+            .map(|id| store.user(&id).map(|user| (id, user.map(|u| u.profile))))
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .await;
+    let profiles = match profiles {
+        Ok(Ok(profiles)) => profiles,
+        Ok(Err(e)) => return storage_error(e),
+        Err(e) => return storage_error(e),
+    };
+    for (id, profile) in profiles {
+        let Some(profile) = profile else {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": format!("unknown player {id}") })),
+            );
+        };
+        if let Some(player) = req.players.iter_mut().find(|p| p.id == id) {
+            player.name = profile.display_name;
+        }
+    }
 
+    match launch_room(&state, req, Some(caller)).await {
+        Ok(game_id) => (
+            StatusCode::OK,
+            Json(json!({"success": true, "game_id": game_id}))
+        ),
+        Err((status, error)) => (status, Json(json!({ "error": error }))),
+    }
+}
+
+// 创建对局记录并启动房间，/start 和匹配队列共用；返回 game_id
+pub async fn launch_room(
+    state: &AppState,
+    req: StartGameRequest,
+    creator: Option<String>,
+) -> Result<i64, (StatusCode, String)> {
+    let settings = &state.config;
+
+    if state.shutting_down.load(Ordering::SeqCst) {
+        return Err((StatusCode::SERVICE_UNAVAILABLE, "server is shutting down".to_string()));
+    }
+    {
+        let dispatchers = state.dispatchers.lock().await;
+        if dispatchers.len() >= settings.rooms.max_rooms {
+            return Err((StatusCode::SERVICE_UNAVAILABLE, "room limit reached".to_string()));
+        }
+        if dispatchers.iter().any(|d| d.room_id == req.room_id) {
+            return Err(room_exists(&req.room_id));
+        }
+    }
+
+    let rules = &settings.rules;
+    let player_num = req.players.len() as u32;
+    let pot = req.pot.unwrap_or(rules.pot);
     let config = GameConfig {
         room_name: req.room_name,
         room_id: req.room_id.clone(),
-        players: req.players.clone().into_iter().map(|p| Info {
+        players: req.players.into_iter().map(|p| Info {
             id: p.id,
             name: p.name,
         }).collect(),
-        starting_cash: 350,
-        ante_amount: if player_num == 4 { 50 } else { 40 },
-        pot: 200,
-        trading_duration_secs: 240,
+        starting_cash: req.starting_cash.unwrap_or(rules.starting_cash),
+        // 底池由所有玩家平摊：4 人各 50，5 人各 40；除不尽时余数由第一个座位补上
+        ante_amount: pot.checked_div(player_num).unwrap_or(0),
+        pot,
+        trading_duration_secs: req.trading_duration_secs.unwrap_or(rules.trading_duration_secs),
+        rounds: req.rounds.unwrap_or(rules.rounds),
+        deck: req.deck.unwrap_or(rules.deck),
+        leftover_policy: req.leftover_policy.unwrap_or(rules.leftover_policy),
     };
-    let game = Game::new(config);
-    println!("Game Created..");
-
-    let (dispatcher_sender, dispatcher_receiver) = channel(64);
-    let mut dispatcher = Dispatcher {
-        room_id: req.room_id,
-        game: game,
-        receiver: dispatcher_receiver,
-        participants: HashMap::new(),
-        last_activity: Instant::now(),
+    let game = Game::new(config).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let store = state.store.clone();
+    let room = game.game_config.clone();
+    let started_at = game.clock().now().as_millis() as u64;
+    let rated = req.rated.unwrap_or(true);
+    let game_id = tokio::task::spawn_blocking(move || store.create_game(&room, started_at, rated))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|created| created.map_err(|e| e.to_string()))
+        .map_err(|e| {
+            log::error!("storage error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "storage error".to_string())
+        })?;
+
+    let absent_quote_policy = req.absent_quote_policy.unwrap_or(rules.absent_quote_policy);
+    let limits = req.limits.unwrap_or(state.config.limits);
+    if let Err((status, e)) = open_room(state, game, Some(game_id), absent_quote_policy, limits, creator, true).await {
+        log::error!("failed to open room, room id: {}, reason: {}", req.room_id, e);
+        return Err((status, e));
+    }
+    Ok(game_id)
+}
+
+fn room_exists(room_id: &str) -> (StatusCode, String) {
+    (StatusCode::CONFLICT, format!("room {room_id} already exists"))
+}
+
+fn storage_error(e: impl std::fmt::Display) -> (StatusCode, Json<serde_json::Value>) {
+    log::error!("storage error: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": "storage error" })),
+    )
+}
+
+async fn game_record(
+    State(state): State<AppState>,
+    AuthUser(caller): AuthUser,
+    Path(game_id): Path<i64>,
+) -> impl IntoResponse {
+    let store = state.store.clone();
+    match tokio::task::spawn_blocking(move || store.game(game_id)).await {
+        Ok(Ok(Some(mut record))) => {
+            // 私聊只给双方看
+            record.chat.retain(|m| m.to.is_none() || m.from == caller || m.to.as_ref() == Some(&caller));
+            (StatusCode::OK, Json(json!(record)))
+        }
+        Ok(Ok(None)) => (StatusCode::NOT_FOUND, Json(json!({ "error": "game not found" }))),
+        Ok(Err(e)) => storage_error(e),
+        Err(e) => storage_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryParams {
+    limit: Option<u32>,
+}
+
+async fn player_history(
+    State(state): State<AppState>,
+    AuthUser(_): AuthUser,
+    Path(player_id): Path<String>,
+    Query(params): Query<HistoryParams>,
+) -> impl IntoResponse {
+    let store = state.store.clone();
+    let limit = params.limit.unwrap_or(50).min(500);
+    match tokio::task::spawn_blocking(move || store.player_history(&player_id, limit)).await {
+        Ok(Ok(history)) => (StatusCode::OK, Json(json!(history))),
+        Ok(Err(e)) => storage_error(e),
+        Err(e) => storage_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LeaderboardParams {
+    #[serde(default)]
+    ladder: Ladder,
+    #[serde(default)]
+    period: Period,
+    // 只看 4 人或 5 人局
+    players: Option<u32>,
+    limit: Option<u32>,
+}
+
+async fn leaderboard(
+    State(state): State<AppState>,
+    AuthUser(_): AuthUser,
+    Query(params): Query<LeaderboardParams>,
+) -> impl IntoResponse {
+    let query = LeaderboardQuery {
+        ladder: params.ladder,
+        since_millis: params.period.since_millis(SystemClock.now().as_millis() as u64),
+        players: params.players,
+        limit: params.limit.unwrap_or(50).min(500),
+    };
+    let store = state.store.clone();
+    match tokio::task::spawn_blocking(move || store.leaderboard(&query)).await {
+        Ok(Ok(entries)) => (StatusCode::OK, Json(json!(entries))),
+        Ok(Err(e)) => storage_error(e),
+        Err(e) => storage_error(e),
+    }
+}
+
+// 为 game 创建 dispatcher、分配座位并启动房间；新房间 start 为 true，从快照恢复的房间保持原有阶段
+async fn open_room(
+    state: &AppState,
+    mut game: Game,
+    game_id: Option<i64>,
+    absent_quote_policy: AbsentQuotePolicy,
+    limits: FloodLimits,
+    creator: Option<String>,
+    start: bool,
+) -> Result<(), (StatusCode, String)> {
+    let settings = &state.config;
+    let room_id = game.room_id().to_string();
+    let (dispatcher_sender, dispatcher_receiver) = channel(settings.rooms.command_queue.max(1));
+    let room = Arc::new(game.game_config.clone());
+
+    // 生成初始 RoundStarted events
+    let events = if start {
+        game.start_round(1).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    } else {
+        vec![]
     };
 
-    for player in req.players.into_iter() {
-        let (participant, event_sender) = create_participant(player.id.clone(), dispatcher_sender.clone());
-        dispatcher.register(player.id.clone(), event_sender);
-
-        if player.id.starts_with("robot") {
-            let player_state = dispatcher
-                .game
-                .state
-                .players
-                .iter()
-                .find(|p| p.info.id == player.id)
-                .expect("robot player not found");
+    // 检查和登记在同一把锁下完成，并发创建同名房间时只有一个成功
+    {
+        let mut dispatchers = state.dispatchers.lock().await;
+        if dispatchers.iter().any(|d| d.room_id == room_id) {
+            return Err(room_exists(&room_id));
+        }
+        dispatchers.push(RoomHandle {
+            room_id: room_id.clone(),
+            room: room.clone(),
+            commands: dispatcher_sender.clone(),
+        });
+    }
+
+    let mut dispatcher = Dispatcher::new(room_id.clone(), game, dispatcher_receiver);
+    dispatcher.absent_quote_policy = absent_quote_policy;
+    dispatcher.flood_limits = limits;
+    dispatcher.creator = creator.clone();
+    dispatcher.recorder = game_id.map(|id| Recorder::new(id, state.records.clone()));
+    dispatcher.chat = ChatPolicy::new(&settings.chat);
+    if settings.timeouts.robot_takeover_secs > 0 {
+        dispatcher.takeover = Some(Takeover {
+            after: Duration::from_secs(settings.timeouts.robot_takeover_secs),
+            commands: dispatcher_sender.clone(),
+            params: settings.robots,
+            outbox_capacity: settings.rooms.outbox_capacity,
+        });
+    }
+
+    for info in room.players.iter() {
+        let (mut participant, event_sender) = create_participant(
+            info.id.clone(), room.clone(), dispatcher_sender.clone(), settings.rooms.outbox_capacity,
+        );
+        participant.limits = limits;
+        participant.controls = creator.as_ref() == Some(&info.id);
+        dispatcher.register(info.id.clone(), event_sender);
+
+        if info.id.starts_with("robot") {
+            let Ok(player_state) = dispatcher.game.player(&info.id) else {
+                log::error!("robot {} not found in room {}", info.id, room_id);
+                continue;
+            };
             let hand = player_state.hand.clone();
             let cash = player_state.cash;
-            let Participant {
-                player_id,
-                action_sender,
-                event_receiver,
-            } = participant;
+            let clock = dispatcher.clock.clone();
+            let params = settings.robots;
             tokio::spawn(async move {
-                robot_loop(player_id, hand, cash, event_receiver, action_sender).await;
+                robot_loop(participant, hand, cash, clock, params).await;
             });
         } else {
-            human_participants
+            state.human_participants
                 .lock()
                 .await
                 .push(participant);
         }
     }
 
-    let dispatcher = Arc::new(Mutex::new(dispatcher));
-    dispatchers.lock().await.push(dispatcher.clone());
+    dispatcher.handover_events(events);
 
-    // 生成初始 RoundStarted events
-    let mut dispatcher_lock = dispatcher.lock().await;
-    let events = dispatcher_lock.game.start_round(1);
-    dispatcher_lock.handover_events(events).await;
-
-    tokio::spawn( {
-        let dispatcher = dispatcher.clone();
-        async move {
-            let mut dispatcher = dispatcher.lock().await; // ✅ 这里是 Future
-            dispatcher.run().await;
-        }
+    let dispatchers = state.dispatchers.clone();
+    let matchmaker = state.matchmaker.clone();
+    tokio::spawn(async move {
+        dispatcher.run().await;
+        // 游戏结束后释放房间名额
+        dispatchers.lock().await.retain(|d| !d.commands.same_channel(&dispatcher_sender));
+        matchmaker.lock().await.room_closed(&room_id);
     });
+    Ok(())
+}
 
-    (
-        StatusCode::OK,
-        Json(json!({"success": true}))
-    )
+// 启动时恢复上次停机保存的房间
+pub async fn restore_rooms(state: &AppState) {
+    for (path, snapshot) in snapshot::load_all(&state.config.server.data_dir) {
+        let room_id = snapshot.room_id.clone();
+        let game = match Game::restore(snapshot.game, Arc::new(SystemClock)) {
+            Ok(game) => game,
+            Err(e) => {
+                log::error!("failed to restore room {}: {}", room_id, e);
+                snapshot::mark_failed(&path);
+                continue;
+            }
+        };
+        match open_room(state, game, snapshot.game_id, snapshot.absent_quote_policy, snapshot.limits, snapshot.creator, false).await {
+            Ok(()) => {
+                log::info!("room {} restored", room_id);
+                snapshot::remove(&path);
+            }
+            Err((_, e)) => {
+                log::error!("failed to restore room {}: {}", room_id, e);
+                snapshot::mark_failed(&path);
+            }
+        }
+    }
+}
+
+// 停机：不再创建新房间，通知每个房间并把未结束的游戏写到磁盘
+pub async fn shutdown(state: &AppState) {
+    state.shutting_down.store(true, Ordering::SeqCst);
+    let rooms: Vec<RoomHandle> = state.dispatchers.lock().await.clone();
+    log::info!("shutting down {} room(s)", rooms.len());
+
+    for room in rooms {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        if room.commands.send(Command::Shutdown(reply_tx)).await.is_err() {
+            continue;
+        }
+        let snapshot = match tokio::time::timeout(SHUTDOWN_TIMEOUT, reply_rx).await {
+            Ok(Ok(Some(snapshot))) => snapshot,
+            Ok(_) => continue,
+            Err(_) => {
+                log::error!("room {} did not answer the shutdown request", room.room_id);
+                continue;
+            }
+        };
+        match snapshot::save(&state.config.server.data_dir, &snapshot) {
+            Ok(path) => log::info!("room {} saved to {}", room.room_id, path.display()),
+            Err(e) => log::error!("failed to save room {}: {}", room.room_id, e),
+        }
+    }
+}
+
+async fn protocol_schema() -> impl IntoResponse {
+    Json(schema())
+}
+
+#[derive(Debug, Deserialize)]
+struct ConnectParams {
+    format: Option<WireFormat>,
 }
 
 async fn ws_connect(
     State(state): State<AppState>,
-    ws: WebSocketUpgrade,
+    AuthUser(caller): AuthUser,
     Path((room_id, player_id)): Path<(String, String)>,
+    Query(params): Query<ConnectParams>,
+    ws: WebSocketUpgrade,
 ) -> impl IntoResponse {
+    // token 只能用于自己的座位
+    if caller != player_id {
+        log::warn!("seat mismatch, room id: {}, player id: {}, token for: {}", room_id, player_id, caller);
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
     let human_participants = state.human_participants.clone();
+    let timeouts = state.config.timeouts.clone();
+    let is_admin = state.config.admin.players.contains(&player_id);
+
+    // 握手前就按房间的限制设置消息和帧的上限，超长的消息不会被整条读进内存
+    let limits = seat_limits(&human_participants, &room_id, &player_id)
+        .await
+        .unwrap_or(state.config.limits);
+    let ws = ws
+        .max_message_size(limits.max_message_bytes)
+        .max_frame_size(limits.max_message_bytes);
+
+    // 子协议优先，其次是 ?format=，都没有时使用 JSON
+    let ws = ws.protocols(WireFormat::SUBPROTOCOLS);
+    let format = ws
+        .selected_protocol()
+        .and_then(|p| p.to_str().ok())
+        .and_then(WireFormat::from_subprotocol)
+        .or(params.format)
+        .unwrap_or_default();
 
-    ws.on_upgrade(move | mut socket| async move {
-        println!("WebSocket connected, room_id = {}, player_id = {}", room_id, player_id);
+    ws.on_upgrade(move |socket| async move {
+        log::info!("websocket connected, room id: {}, player id: {}, format: {:?}", room_id, player_id, format);
 
-        let participant = {
-            let mut vec = human_participants.lock().await;
-            let idx = vec.iter().position(|p| p.player_id == player_id)
-                .expect("participant not found");
-            vec.remove(idx) // 直接拿走，防止重复连接
+        let Some(mut participant) = take_participant(&human_participants, &room_id, &player_id).await else {
+            log::warn!("participant not found, room id: {}, player id: {}", room_id, player_id);
+            return;
         };
 
-        let mut event_rx = participant.event_receiver;
-        let action_tx = participant.action_sender;
+        let _connection = ConnectionGuard::new("websocket");
+        let action_tx = participant.action_sender.clone();
+        let controls = participant.controls || is_admin;
+        let _ = action_tx.send(Command::Connected(player_id.clone())).await;
+
+        // recv 一侧产生的 Ack/Nack 统一交给 send 一侧写出
+        let (control_tx, mut control_rx) = channel::<ControlMessage>(16);
+        let _ = control_tx.send(hello(&player_id, &participant.room)).await;
 
         let (mut ws_tx, mut ws_rx) = socket.split();
+        // 最近一次收到客户端任何消息（包括 Pong）的时间
+        let last_seen = std::sync::Mutex::new(Instant::now());
+        let event_rx = &mut participant.event_receiver;
 
-        // 2️⃣ event → websocket
-        let send_task = tokio::spawn(async move {
-            while let Some(event) = event_rx.recv().await {
-                let json: Value = event_to_json(&event);
-                let text = json.to_string();
-                let _ = ws_tx.send(Message::Text(text.into())).await;
+        // 2️⃣ event / control → websocket，并定时 ping
+        let send_loop = async {
+            let mut heartbeat = interval(Duration::from_secs(timeouts.heartbeat_interval_secs.max(1)));
+            loop {
+                let message: ServerMessage = select! {
+                    Some(control) = control_rx.recv() => control.into(),
+                    event = event_rx.recv() => match event {
+                        Some(event) => event.into(),
+                        // 消费过慢被 dispatcher 断开，或房间已结束
+                        None => break,
+                    },
+                    _ = heartbeat.tick() => {
+                        let idle = last_seen.lock().map(|t| t.elapsed()).unwrap_or_default();
+                        if idle > Duration::from_secs(timeouts.heartbeat_timeout_secs) {
+                            log::info!("heartbeat timeout, room id: {}, player id: {}", room_id, player_id);
+                            break;
+                        }
+                        if ws_tx.send(Message::Ping(Default::default())).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                    else => break,
+                };
+                let encoded = match format {
+                    WireFormat::Json => server_message_to_text(&message)
+                        .map(|text| Message::Text(text.into()))
+                        .map_err(|e| e.to_string()),
+                    WireFormat::Msgpack => server_message_to_msgpack(&message)
+                        .map(|bytes| Message::Binary(bytes.into()))
+                        .map_err(|e| e.to_string()),
+                };
+                let encoded = match encoded {
+                    Ok(encoded) => encoded,
+                    Err(e) => {
+                        log::error!("failed to encode server message: {}", e);
+                        continue;
+                    }
+                };
+                if ws_tx.send(encoded).await.is_err() {
+                    break;
+                }
             }
-        });
+        };
 
-        // 3️⃣ websocket → action
-        let recv_task = tokio::spawn(async move {
-            while let Some(Ok(Message::Text(text))) = ws_rx.next().await {
-                if let Ok(view) = serde_json::from_str::<ActionView>(&text) {
-                    if let Ok(action) = Action::try_from(view) {
-                        let _ = action_tx.send(action).await;
+        // 3️⃣ websocket → action；返回 true 表示因反复超限被踢出
+        let mut flood = FloodGuard::new(participant.limits, Instant::now());
+        let recv_loop = async {
+            while let Some(Ok(message)) = ws_rx.next().await {
+                if let Ok(mut t) = last_seen.lock() {
+                    *t = Instant::now();
+                }
+                // 超限的消息在进入 dispatcher 队列之前就拒绝
+                let size = match &message {
+                    Message::Text(text) => text.len(),
+                    Message::Binary(bytes) => bytes.len(),
+                    _ => 0,
+                };
+                if size > 0 && let Err(violation) = flood.check(size, Instant::now()) {
+                    if violation == Violation::Kick {
+                        return true;
+                    }
+                    let id = match (&violation, &message) {
+                        (Violation::RateLimited, Message::Text(text)) => text_request_id(text),
+                        (Violation::RateLimited, Message::Binary(bytes)) => binary_request_id(bytes),
+                        _ => None,
+                    };
+                    if control_tx.send(violation.nack(id)).await.is_err() {
+                        break;
                     }
+                    continue;
+                }
+                let reply = match message {
+                    Message::Text(text) => handle_client_text(&text, &player_id, controls, &action_tx).await,
+                    Message::Binary(bytes) if format == WireFormat::Msgpack => {
+                        handle_client_binary(&bytes, &player_id, controls, &action_tx).await
+                    }
+                    Message::Binary(_) => Some(nack(None, "MalformedMessage", "binary frames require the msgpack format")),
+                    Message::Close(_) => break,
+                    // Ping 由 axum 自动回复，Pong 只用于刷新 last_seen
+                    _ => None,
+                };
+                if let Some(reply) = reply
+                    && control_tx.send(reply).await.is_err() {
+                    break;
                 }
             }
-        });
+            false
+        };
 
-        let _ = tokio::join!(send_task, recv_task);
+        // 任意一侧结束即视为断开
+        let kicked = select! {
+            _ = send_loop => false,
+            kicked = recv_loop => kicked,
+        };
+
+        if kicked {
+            let close = CloseFrame { code: close_code::POLICY, reason: "too many rejected messages".into() };
+            let _ = ws_tx.send(Message::Close(Some(close))).await;
+            limits::kick(&action_tx, participant.room.clone(), &player_id).await;
+        }
+        let _ = action_tx.send(Command::Disconnected(player_id.clone())).await;
+        // 放回等待队列，玩家可以重新连接；座位被换掉或房间已结束时不再放回
+        if !participant.event_receiver.is_closed() {
+            human_participants.lock().await.push(participant);
+        }
 
-        println!("Player {} websocket disconnected", player_id);
+        log::info!("websocket disconnected, room id: {}, player id: {}", room_id, player_id);
     })
-}
\ No newline at end of file
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::Store;
+
+    fn request(room_id: &str) -> StartGameRequest {
+        StartGameRequest {
+            room_name: "dup".to_string(),
+            room_id: room_id.to_string(),
+            players: (1..=4).map(|i| PlayerInfo { id: format!("robot{i}"), name: format!("Robot {i}") }).collect(),
+            ..StartGameRequest::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_duplicate_room_ids() {
+        let state = AppState::new(ServerConfig::default(), Store::in_memory().unwrap(), Sessions::new(vec![7; 32], 60));
+        launch_room(&state, request("R1"), None).await.unwrap();
+        let (status, error) = launch_room(&state, request("R1"), None).await.unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(error.contains("R1"));
+        assert_eq!(state.dispatchers.lock().await.len(), 1);
+
+        launch_room(&state, request("R2"), None).await.unwrap();
+        assert_eq!(state.dispatchers.lock().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn only_the_creator_seat_controls_the_game() {
+        let state = AppState::new(ServerConfig::default(), Store::in_memory().unwrap(), Sessions::new(vec![7; 32], 60));
+        let mut req = request("R1");
+        req.players[0] = PlayerInfo { id: "alice".to_string(), name: "Alice".to_string() };
+        req.players[1] = PlayerInfo { id: "bob".to_string(), name: "Bob".to_string() };
+        launch_room(&state, req, Some("alice".to_string())).await.unwrap();
+
+        let seats = state.human_participants.lock().await;
+        let controls: Vec<_> = seats.iter().map(|p| (p.player_id.as_str(), p.controls)).collect();
+        assert_eq!(controls, vec![("alice", true), ("bob", false)]);
+    }
+
+    // 停机保存后恢复的房间，创建者的座位仍然可以控制对局
+    #[tokio::test]
+    async fn restored_rooms_keep_their_creator() {
+        let mut config = ServerConfig::default();
+        config.server.data_dir = std::env::temp_dir().join(format!("figgie-restore-{}", std::process::id()));
+        let sessions = || Sessions::new(vec![7; 32], 60);
+        let state = AppState::new(config.clone(), Store::in_memory().unwrap(), sessions());
+        let mut req = request("R-RESTORE");
+        req.players[0] = PlayerInfo { id: "alice".to_string(), name: "Alice".to_string() };
+        launch_room(&state, req, Some("alice".to_string())).await.unwrap();
+        shutdown(&state).await;
+
+        let restored = AppState::new(config.clone(), Store::in_memory().unwrap(), sessions());
+        restore_rooms(&restored).await;
+        assert_eq!(restored.dispatchers.lock().await.len(), 1);
+        let seat = take_participant(&restored.human_participants, "R-RESTORE", "alice").await.unwrap();
+        assert!(seat.controls);
+
+        let _ = std::fs::remove_dir_all(&config.server.data_dir);
+    }
+}
@@ -0,0 +1,378 @@
+use crate::app::AppState;
+use crate::store::Profile;
+
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::password_hash::SaltString;
+use axum::{
+    extract::{FromRequestParts, Path as UrlPath, Query, State},
+    http::{header, request::Parts, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+// 未配置 auth.secret 时，签名密钥保存在 <data_dir>/auth.key
+const KEY_FILE: &str = "auth.key";
+const KEY_LEN: usize = 32;
+
+const MIN_PASSWORD_LEN: usize = 8;
+const MAX_PASSWORD_LEN: usize = 128;
+const MAX_DISPLAY_NAME_LEN: usize = 32;
+const MAX_URL_LEN: usize = 512;
+const MAX_EMAIL_LEN: usize = 254;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: String,
+    exp: u64,
+}
+
+// 会话 token：base64url(claims).base64url(HMAC-SHA256)，sub 即游戏中的 player_id
+pub struct Sessions {
+    key: Vec<u8>,
+    ttl_secs: u64,
+}
+
+impl Sessions {
+    pub fn new(key: Vec<u8>, ttl_secs: u64) -> Self {
+        Sessions { key, ttl_secs }
+    }
+
+    // 优先使用配置中的密钥，否则读取或生成密钥文件，重启后已签发的 token 仍然有效
+    pub fn load(secret: &str, data_dir: &Path, ttl_secs: u64) -> std::io::Result<Self> {
+        if !secret.is_empty() {
+            return Ok(Self::new(secret.as_bytes().to_vec(), ttl_secs));
+        }
+        let path = data_dir.join(KEY_FILE);
+        if let Ok(text) = std::fs::read_to_string(&path) {
+            let key = STANDARD.decode(text.trim()).map_err(std::io::Error::other)?;
+            return Ok(Self::new(key, ttl_secs));
+        }
+        let mut key = vec![0u8; KEY_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut key);
+        std::fs::write(&path, STANDARD.encode(&key))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        log::info!("generated session key at {}", path.display());
+        Ok(Self::new(key, ttl_secs))
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        HmacSha256::new_from_slice(&self.key).expect("hmac accepts any key length")
+    }
+
+    pub fn issue(&self, player_id: &str, now: u64) -> String {
+        let claims = Claims { sub: player_id.to_string(), exp: now + self.ttl_secs };
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap_or_default());
+        let mut mac = self.mac();
+        mac.update(payload.as_bytes());
+        let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+        format!("{payload}.{signature}")
+    }
+
+    // 签名正确且未过期时返回 player_id
+    pub fn verify(&self, token: &str, now: u64) -> Option<String> {
+        let (payload, signature) = token.split_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+        let mut mac = self.mac();
+        mac.update(payload.as_bytes());
+        mac.verify_slice(&signature).ok()?;
+        let claims: Claims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+        (claims.exp > now).then_some(claims.sub)
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+// 用户不存在时拿它校验，登录耗时与用户存在时相同，不暴露哪些用户名已注册
+static DUMMY_HASH: LazyLock<String> = LazyLock::new(|| hash_password("figgie").unwrap_or_default());
+
+pub fn verify_password(hash: &str, password: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+        .unwrap_or(false)
+}
+
+// player_id 同时用作登录名，robot 前缀留给服务端机器人
+fn check_player_id(player_id: &str) -> Result<(), String> {
+    let valid_chars = player_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !(3..=32).contains(&player_id.len()) || !valid_chars {
+        return Err("player_id must be 3-32 characters of letters, digits, '_' or '-'".to_string());
+    }
+    if player_id.to_ascii_lowercase().starts_with("robot") {
+        return Err("player_id must not start with 'robot'".to_string());
+    }
+    Ok(())
+}
+
+fn check_profile(profile: &Profile) -> Result<(), String> {
+    let name_len = profile.display_name.chars().count();
+    if profile.display_name.trim().is_empty() || name_len > MAX_DISPLAY_NAME_LEN {
+        return Err(format!("display_name must be 1-{MAX_DISPLAY_NAME_LEN} characters"));
+    }
+    if let Some(url) = &profile.avatar_url
+        && (url.len() > MAX_URL_LEN || !(url.starts_with("https://") || url.starts_with("http://")))
+    {
+        return Err("avatar_url must be an http(s) URL".to_string());
+    }
+    if let Some(email) = &profile.email
+        && (email.len() > MAX_EMAIL_LEN || !email.contains('@') || email.contains(char::is_whitespace))
+    {
+        return Err("invalid email".to_string());
+    }
+    Ok(())
+}
+
+// 空字符串表示清除该字段
+fn optional(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+type ApiError = (StatusCode, Json<Value>);
+
+fn error(status: StatusCode, message: impl ToString) -> ApiError {
+    (status, Json(json!({ "error": message.to_string() })))
+}
+
+fn internal(e: impl std::fmt::Display) -> ApiError {
+    log::error!("account error: {}", e);
+    error(StatusCode::INTERNAL_SERVER_ERROR, "internal error")
+}
+
+// 已登录的玩家：Authorization: Bearer <token>，浏览器的 WebSocket 无法带请求头，也可以用 ?token=
+pub struct AuthUser(pub String);
+
+#[derive(Deserialize)]
+struct TokenParams {
+    token: Option<String>,
+}
+
+impl FromRequestParts<AppState> for AuthUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let header_token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(|t| t.trim().to_string());
+        let token = header_token.or_else(|| {
+            Query::<TokenParams>::try_from_uri(&parts.uri).ok().and_then(|q| q.0.token)
+        });
+        let Some(token) = token else {
+            return Err(error(StatusCode::UNAUTHORIZED, "missing token"));
+        };
+        state
+            .sessions
+            .verify(&token, unix_now())
+            .map(AuthUser)
+            .ok_or_else(|| error(StatusCode::UNAUTHORIZED, "invalid or expired token"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterRequest {
+    player_id: String,
+    password: String,
+    display_name: Option<String>,
+    avatar_url: Option<String>,
+    email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    player_id: String,
+    password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProfileUpdate {
+    display_name: Option<String>,
+    avatar_url: Option<String>,
+    email: Option<String>,
+}
+
+pub async fn register(State(state): State<AppState>, Json(req): Json<RegisterRequest>) -> Result<impl IntoResponse, ApiError> {
+    check_player_id(&req.player_id).map_err(|e| error(StatusCode::BAD_REQUEST, e))?;
+    if !(MIN_PASSWORD_LEN..=MAX_PASSWORD_LEN).contains(&req.password.len()) {
+        return Err(error(
+            StatusCode::BAD_REQUEST,
+            format!("password must be {MIN_PASSWORD_LEN}-{MAX_PASSWORD_LEN} bytes"),
+        ));
+    }
+    let profile = Profile {
+        display_name: optional(req.display_name).unwrap_or_else(|| req.player_id.clone()),
+        player_id: req.player_id,
+        avatar_url: optional(req.avatar_url),
+        email: optional(req.email),
+    };
+    check_profile(&profile).map_err(|e| error(StatusCode::BAD_REQUEST, e))?;
+
+    let store = state.store.clone();
+    let stored = profile.clone();
+    let created = tokio::task::spawn_blocking(move || {
+        let hash = hash_password(&req.password)?;
+        store.create_user(&hash, &stored, unix_now()).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(internal)?
+    .map_err(internal)?;
+    if !created {
+        return Err(error(StatusCode::CONFLICT, "player_id already taken"));
+    }
+
+    log::info!("account registered, player id: {}", profile.player_id);
+    let token = state.sessions.issue(&profile.player_id, unix_now());
+    Ok((StatusCode::CREATED, Json(json!({ "token": token, "profile": profile }))))
+}
+
+pub async fn login(State(state): State<AppState>, Json(req): Json<LoginRequest>) -> Result<impl IntoResponse, ApiError> {
+    let store = state.store.clone();
+    let player_id = req.player_id.clone();
+    let profile = tokio::task::spawn_blocking(move || {
+        store.user(&player_id).map(|user| match user {
+            Some(user) => verify_password(&user.password_hash, &req.password).then_some(user.profile),
+            None => {
+                verify_password(&DUMMY_HASH, &req.password);
+                None
+            }
+        })
+    })
+    .await
+    .map_err(internal)?
+    .map_err(internal)?;
+
+    let Some(profile) = profile else {
+        log::warn!("login failed, player id: {}", req.player_id);
+        return Err(error(StatusCode::UNAUTHORIZED, "invalid credentials"));
+    };
+    let token = state.sessions.issue(&profile.player_id, unix_now());
+    Ok(Json(json!({ "token": token, "profile": profile })))
+}
+
+async fn load_profile(state: &AppState, player_id: String) -> Result<Profile, ApiError> {
+    let store = state.store.clone();
+    tokio::task::spawn_blocking(move || store.user(&player_id))
+        .await
+        .map_err(internal)?
+        .map_err(internal)?
+        .map(|user| user.profile)
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "player not found"))
+}
+
+pub async fn me(State(state): State<AppState>, AuthUser(player_id): AuthUser) -> Result<impl IntoResponse, ApiError> {
+    Ok(Json(load_profile(&state, player_id).await?))
+}
+
+pub async fn update_me(
+    State(state): State<AppState>,
+    AuthUser(player_id): AuthUser,
+    Json(update): Json<ProfileUpdate>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut profile = load_profile(&state, player_id).await?;
+    if let Some(name) = update.display_name {
+        profile.display_name = name.trim().to_string();
+    }
+    if update.avatar_url.is_some() {
+        profile.avatar_url = optional(update.avatar_url);
+    }
+    if update.email.is_some() {
+        profile.email = optional(update.email);
+    }
+    check_profile(&profile).map_err(|e| error(StatusCode::BAD_REQUEST, e))?;
+
+    let store = state.store.clone();
+    let stored = profile.clone();
+    tokio::task::spawn_blocking(move || store.update_profile(&stored))
+        .await
+        .map_err(internal)?
+        .map_err(internal)?;
+    Ok(Json(profile))
+}
+
+// 其他玩家的公开资料，不含 email
+pub async fn profile(
+    State(state): State<AppState>,
+    AuthUser(_): AuthUser,
+    UrlPath(player_id): UrlPath<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut profile = load_profile(&state, player_id).await?;
+    profile.email = None;
+    Ok(Json(profile))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_round_trip_and_expire() {
+        let sessions = Sessions::new(b"secret".to_vec(), 60);
+        let token = sessions.issue("alice", 1_000);
+        assert_eq!(sessions.verify(&token, 1_030).as_deref(), Some("alice"));
+        assert!(sessions.verify(&token, 1_060).is_none());
+
+        // 换了密钥或改了内容都无法通过校验
+        assert!(Sessions::new(b"other".to_vec(), 60).verify(&token, 1_030).is_none());
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = URL_SAFE_NO_PAD.encode(br#"{"sub":"bob","exp":9999999999}"#);
+        assert!(sessions.verify(&format!("{forged}.{signature}"), 1_030).is_none());
+        assert!(sessions.verify("garbage", 1_030).is_none());
+    }
+
+    #[test]
+    fn passwords_are_hashed() {
+        let hash = hash_password("correct horse").unwrap();
+        assert!(!hash.contains("correct horse"));
+        assert!(verify_password(&hash, "correct horse"));
+        assert!(!verify_password(&hash, "wrong horse"));
+        assert!(!verify_password("not a hash", "correct horse"));
+        assert!(PasswordHash::new(&DUMMY_HASH).is_ok());
+        assert!(!verify_password(&DUMMY_HASH, "correct horse"));
+    }
+
+    #[test]
+    fn validates_accounts() {
+        assert!(check_player_id("alice_01").is_ok());
+        assert!(check_player_id("al").is_err());
+        assert!(check_player_id("robot7").is_err());
+        assert!(check_player_id("a b c").is_err());
+
+        let mut profile = Profile {
+            player_id: "alice".to_string(),
+            display_name: "Alice".to_string(),
+            ..Profile::default()
+        };
+        assert!(check_profile(&profile).is_ok());
+        profile.avatar_url = Some("javascript:alert(1)".to_string());
+        assert!(check_profile(&profile).is_err());
+        profile.avatar_url = None;
+        profile.email = Some("nope".to_string());
+        assert!(check_profile(&profile).is_err());
+    }
+}
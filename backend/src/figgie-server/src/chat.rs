@@ -0,0 +1,177 @@
+use crate::config::ChatSection;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+// 聊天被服务端策略拒绝；这些不是游戏规则，不放在 core 的 FiggieError 里
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ChatError {
+    MessageTooLong { len: usize, max: usize },
+    RateLimited,
+    Muted,
+    // 默认过滤器只替换屏蔽词，整条拦截留给自定义的 ChatFilter
+    #[allow(dead_code)]
+    MessageBlocked,
+}
+
+impl fmt::Display for ChatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChatError::MessageTooLong { len, max } => write!(f, "message has {len} characters, limit is {max}"),
+            ChatError::RateLimited => write!(f, "too many messages, slow down"),
+            ChatError::Muted => write!(f, "player is muted"),
+            ChatError::MessageBlocked => write!(f, "message blocked by the chat filter"),
+        }
+    }
+}
+
+impl ChatError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ChatError::MessageTooLong { .. } => "MessageTooLong",
+            ChatError::RateLimited => "RateLimited",
+            ChatError::Muted => "Muted",
+            ChatError::MessageBlocked => "MessageBlocked",
+        }
+    }
+}
+
+impl std::error::Error for ChatError {}
+
+// 内容过滤钩子：返回实际发出的文本，或者拒绝这条消息（禁言、违规内容等）
+pub trait ChatFilter: Send + Sync + std::fmt::Debug {
+    fn filter(&self, player_id: &str, text: &str) -> Result<String, ChatError>;
+}
+
+// 默认过滤器：配置中的禁言名单，屏蔽词替换为 *
+#[derive(Debug, Default)]
+pub struct WordFilter {
+    muted: HashSet<String>,
+    // 小写，按 ASCII 忽略大小写匹配
+    blocked_words: Vec<String>,
+}
+
+impl WordFilter {
+    pub fn new(config: &ChatSection) -> Self {
+        WordFilter {
+            muted: config.muted_players.iter().cloned().collect(),
+            blocked_words: config
+                .blocked_words
+                .iter()
+                .filter(|w| !w.is_empty())
+                .map(|w| w.to_ascii_lowercase())
+                .collect(),
+        }
+    }
+}
+
+impl ChatFilter for WordFilter {
+    fn filter(&self, player_id: &str, text: &str) -> Result<String, ChatError> {
+        if self.muted.contains(player_id) {
+            return Err(ChatError::Muted);
+        }
+        // to_ascii_lowercase 不改变字节长度，匹配到的位置可以直接用在原文上
+        let lower = text.to_ascii_lowercase();
+        let mut masked = text.to_string();
+        for word in &self.blocked_words {
+            for (start, _) in lower.match_indices(word.as_str()) {
+                let end = start + word.len();
+                let stars = "*".repeat(text[start..end].chars().count());
+                masked.replace_range(start..end, &stars);
+            }
+        }
+        Ok(masked)
+    }
+}
+
+// 每个房间一份：长度限制、按玩家的滑动窗口限流和内容过滤
+#[derive(Debug)]
+pub struct ChatPolicy {
+    max_length: usize,
+    rate_limit_messages: usize,
+    rate_limit_window: Duration,
+    pub filter: Arc<dyn ChatFilter>,
+    // 每个玩家窗口内已发送消息的时间
+    sent: HashMap<String, VecDeque<Duration>>,
+}
+
+impl Default for ChatPolicy {
+    fn default() -> Self {
+        ChatPolicy::new(&ChatSection::default())
+    }
+}
+
+impl ChatPolicy {
+    pub fn new(config: &ChatSection) -> Self {
+        ChatPolicy {
+            max_length: config.max_length,
+            rate_limit_messages: config.rate_limit_messages,
+            rate_limit_window: Duration::from_secs(config.rate_limit_window_secs),
+            filter: Arc::new(WordFilter::new(config)),
+            sent: HashMap::new(),
+        }
+    }
+
+    // 通过时返回过滤后的文本，被拒绝的消息不计入限流；空消息原样放行，由引擎拒绝
+    pub fn check(&mut self, player_id: &str, text: &str, now: Duration) -> Result<String, ChatError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(String::new());
+        }
+        let len = text.chars().count();
+        if len > self.max_length {
+            return Err(ChatError::MessageTooLong { len, max: self.max_length });
+        }
+
+        let sent = self.sent.entry(player_id.to_string()).or_default();
+        while sent.front().is_some_and(|t| now.saturating_sub(*t) >= self.rate_limit_window) {
+            sent.pop_front();
+        }
+        if sent.len() >= self.rate_limit_messages {
+            return Err(ChatError::RateLimited);
+        }
+
+        let text = self.filter.filter(player_id, text)?;
+        sent.push_back(now);
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> ChatPolicy {
+        ChatPolicy::new(&ChatSection {
+            max_length: 10,
+            rate_limit_messages: 2,
+            rate_limit_window_secs: 5,
+            blocked_words: vec!["darn".to_string()],
+            muted_players: vec!["troll".to_string()],
+        })
+    }
+
+    #[test]
+    fn limits_length_and_rate() {
+        let mut chat = policy();
+        let t = Duration::from_secs;
+        assert_eq!(chat.check("p1", "  ", t(0)), Ok(String::new()));
+        assert_eq!(chat.check("p1", "way too long!", t(0)), Err(ChatError::MessageTooLong { len: 13, max: 10 }));
+
+        assert!(chat.check("p1", "one", t(0)).is_ok());
+        assert!(chat.check("p1", "two", t(1)).is_ok());
+        assert_eq!(chat.check("p1", "three", t(2)), Err(ChatError::RateLimited));
+        // 其他玩家不受影响，窗口过去后恢复
+        assert!(chat.check("p2", "hi", t(2)).is_ok());
+        assert!(chat.check("p1", "again", t(5)).is_ok());
+    }
+
+    #[test]
+    fn filters_words_and_muted_players() {
+        let mut chat = policy();
+        assert_eq!(chat.check("p1", "DARN it", Duration::ZERO).unwrap(), "**** it");
+        assert_eq!(chat.check("troll", "hello", Duration::ZERO), Err(ChatError::Muted));
+    }
+}
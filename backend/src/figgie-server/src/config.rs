@@ -0,0 +1,410 @@
+use crate::dispatcher::AbsentQuotePolicy;
+use crate::outbox::OUTBOX_CAPACITY;
+use crate::robots::RobotParams;
+use crate::limits::FloodLimits;
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use clap::Parser;
+use figgie_core::{DeckConfig, LeftoverPolicy, DEFAULT_ROUNDS};
+use serde::Deserialize;
+
+// 环境变量覆盖配置文件：FIGGIE__RULES__POT=300 对应 [rules] pot = 300
+const ENV_PREFIX: &str = "FIGGIE__";
+
+#[derive(Debug, Parser)]
+#[command(name = "figgie-server", version, about = "Figgie game server")]
+pub struct Cli {
+    #[arg(short, long, env = "FIGGIE_CONFIG", help = "Path to the TOML config file")]
+    pub config: Option<PathBuf>,
+    #[arg(long, env = "FIGGIE_BIND", help = "HTTP / WebSocket listen address")]
+    pub bind: Option<SocketAddr>,
+    #[arg(long, env = "FIGGIE_LOG_LEVEL", help = "Log filter, e.g. info or figgie_server=debug")]
+    pub log_level: Option<String>,
+    #[arg(long, env = "FIGGIE_DATA_DIR", help = "Directory for persisted data")]
+    pub data_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        env = "FIGGIE_BOT_TOKENS",
+        value_delimiter = ',',
+        value_parser = parse_bot_token,
+        help = "Comma-separated token:player_id pairs accepted by the bot TCP gateway"
+    )]
+    pub bot_tokens: Option<Vec<(String, String)>>,
+    #[arg(long, env = "FIGGIE_STATIC_DIR", help = "Serve the built frontend from this directory")]
+    pub static_dir: Option<PathBuf>,
+    #[arg(long, env = "FIGGIE_TLS_CERT", requires = "tls_key", help = "PEM certificate chain; enables HTTPS / WSS")]
+    pub tls_cert: Option<PathBuf>,
+    #[arg(long, env = "FIGGIE_TLS_KEY", requires = "tls_cert", help = "PEM private key for --tls-cert")]
+    pub tls_key: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ServerSection {
+    pub bind: SocketAddr,
+    // 机器人 TCP 接入地址，bot_tokens 为空时不启用
+    pub bot_bind: SocketAddr,
+    // token → 持有该 token 的机器人可以使用的座位
+    pub bot_tokens: HashMap<String, Vec<String>>,
+    pub log_level: String,
+    pub data_dir: PathBuf,
+    // 前端构建目录（frontend/dist），设置后同一个端口托管整个站点
+    pub static_dir: Option<PathBuf>,
+    // 允许跨域调用 REST 接口的来源，如 https://figgie.example；"*" 为任意来源
+    pub cors_origins: Vec<String>,
+    // 证书和私钥（PEM）都设置时启用 HTTPS / WSS
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+}
+
+impl Default for ServerSection {
+    fn default() -> Self {
+        ServerSection {
+            bind: SocketAddr::from(([127, 0, 0, 1], 8080)),
+            bot_bind: SocketAddr::from(([127, 0, 0, 1], 9090)),
+            bot_tokens: HashMap::new(),
+            log_level: "info".to_string(),
+            data_dir: PathBuf::from("data"),
+            static_dir: None,
+            cors_origins: vec![],
+            tls_cert: None,
+            tls_key: None,
+        }
+    }
+}
+
+// 新房间的默认规则，/start 请求中的字段优先
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RulesSection {
+    pub starting_cash: u32,
+    // 底池由所有玩家平摊
+    pub pot: u32,
+    pub trading_duration_secs: u32,
+    // 打满这么多回合的对局才计入积分
+    pub rounds: u32,
+    pub deck: DeckConfig,
+    pub leftover_policy: LeftoverPolicy,
+    pub absent_quote_policy: AbsentQuotePolicy,
+}
+
+impl Default for RulesSection {
+    fn default() -> Self {
+        RulesSection {
+            starting_cash: 350,
+            pot: 200,
+            trading_duration_secs: 240,
+            rounds: DEFAULT_ROUNDS,
+            deck: DeckConfig::default(),
+            leftover_policy: LeftoverPolicy::default(),
+            absent_quote_policy: AbsentQuotePolicy::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RoomsSection {
+    // 同时存在的房间上限
+    pub max_rooms: usize,
+    // 每个房间 dispatcher 的命令队列长度
+    pub command_queue: usize,
+    // 每个参与者的出站事件队列长度
+    pub outbox_capacity: usize,
+}
+
+impl Default for RoomsSection {
+    fn default() -> Self {
+        RoomsSection {
+            max_rooms: 100,
+            command_queue: 64,
+            outbox_capacity: OUTBOX_CAPACITY,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct TimeoutsSection {
+    // 服务端 ping 间隔
+    pub heartbeat_interval_secs: u64,
+    // 超过这个时间没有收到任何消息就断开
+    pub heartbeat_timeout_secs: u64,
+    // 掉线超过这个时间由机器人代打，重新连上后交还；0 表示不接管
+    pub robot_takeover_secs: u64,
+}
+
+impl Default for TimeoutsSection {
+    fn default() -> Self {
+        TimeoutsSection {
+            heartbeat_interval_secs: 15,
+            heartbeat_timeout_secs: 45,
+            robot_takeover_secs: 60,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ChatSection {
+    // 单条消息的最大字符数
+    pub max_length: usize,
+    // 每个玩家在 rate_limit_window_secs 内最多发送的消息数
+    pub rate_limit_messages: usize,
+    pub rate_limit_window_secs: u64,
+    // 不区分大小写，替换为 *
+    pub blocked_words: Vec<String>,
+    pub muted_players: Vec<String>,
+}
+
+impl Default for ChatSection {
+    fn default() -> Self {
+        ChatSection {
+            max_length: 280,
+            rate_limit_messages: 5,
+            rate_limit_window_secs: 10,
+            blocked_words: vec![],
+            muted_players: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct MatchmakingSection {
+    // 等待超过这个时间后用机器人补齐空位
+    pub robot_fill_secs: u64,
+    // 撮合检查间隔
+    pub tick_millis: u64,
+}
+
+impl Default for MatchmakingSection {
+    fn default() -> Self {
+        MatchmakingSection {
+            robot_fill_secs: 30,
+            tick_millis: 1000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct AuthSection {
+    // 会话 token 的签名密钥；为空时使用 <data_dir>/auth.key，不存在则自动生成
+    pub secret: String,
+    pub token_ttl_secs: u64,
+}
+
+impl Default for AuthSection {
+    fn default() -> Self {
+        AuthSection {
+            secret: String::new(),
+            token_ttl_secs: 7 * 24 * 3600,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct AdminSection {
+    // 可以调用 /admin 接口的账号
+    pub players: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub server: ServerSection,
+    pub rules: RulesSection,
+    pub robots: RobotParams,
+    pub rooms: RoomsSection,
+    pub timeouts: TimeoutsSection,
+    pub limits: FloodLimits,
+    pub auth: AuthSection,
+    pub chat: ChatSection,
+    pub matchmaking: MatchmakingSection,
+    pub admin: AdminSection,
+}
+
+impl ServerConfig {
+    // 优先级：命令行 > 环境变量 > 配置文件 > 默认值
+    pub fn load(cli: &Cli) -> Result<Self, String> {
+        let text = match &cli.config {
+            Some(path) => read_file(path)?,
+            None => String::new(),
+        };
+        let mut config = Self::from_toml(&text, std::env::vars())?;
+        config.apply_cli(cli);
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.server.tls_cert.is_some() != self.server.tls_key.is_some() {
+            return Err("invalid config: server.tls_cert and server.tls_key must be set together".to_string());
+        }
+        // robot 开头的座位由内置机器人使用
+        if let Some(seat) = self.server.bot_tokens.values().flatten().find(|id| id.starts_with("robot")) {
+            return Err(format!("invalid config: server.bot_tokens cannot grant built-in robot seat {seat}"));
+        }
+        Ok(())
+    }
+
+    pub fn from_toml(text: &str, env: impl Iterator<Item = (String, String)>) -> Result<Self, String> {
+        let mut table: toml::Table = text.parse().map_err(|e| format!("invalid config: {e}"))?;
+        for (key, value) in env {
+            if let Some(path) = key.strip_prefix(ENV_PREFIX) {
+                apply_override(&mut table, path, &value)?;
+            }
+        }
+        toml::Value::Table(table)
+            .try_into()
+            .map_err(|e| format!("invalid config: {e}"))
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) {
+        if let Some(bind) = cli.bind {
+            self.server.bind = bind;
+        }
+        if let Some(level) = &cli.log_level {
+            self.server.log_level = level.clone();
+        }
+        if let Some(dir) = &cli.data_dir {
+            self.server.data_dir = dir.clone();
+        }
+        if let Some(cert) = &cli.tls_cert {
+            self.server.tls_cert = Some(cert.clone());
+        }
+        if let Some(key) = &cli.tls_key {
+            self.server.tls_key = Some(key.clone());
+        }
+        if let Some(dir) = &cli.static_dir {
+            self.server.static_dir = Some(dir.clone());
+        }
+        if let Some(pairs) = &cli.bot_tokens {
+            self.server.bot_tokens.clear();
+            for (token, player_id) in pairs {
+                self.server.bot_tokens.entry(token.clone()).or_default().push(player_id.clone());
+            }
+        }
+    }
+}
+
+// token:player_id，一个 token 可以重复出现以授权多个座位
+fn parse_bot_token(raw: &str) -> Result<(String, String), String> {
+    match raw.split_once(':') {
+        Some((token, player_id)) if !token.is_empty() && !player_id.is_empty() => {
+            Ok((token.to_string(), player_id.to_string()))
+        }
+        _ => Err(format!("expected token:player_id, got {raw:?}")),
+    }
+}
+
+fn read_file(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))
+}
+
+// SECTION__KEY 逐级写入表中；值按 TOML 解析，解析不了就当作字符串
+fn apply_override(table: &mut toml::Table, path: &str, raw: &str) -> Result<(), String> {
+    let keys: Vec<String> = path.split("__").map(str::to_lowercase).collect();
+    let Some((last, parents)) = keys.split_last() else {
+        return Ok(());
+    };
+    let mut current = table;
+    for key in parents {
+        let entry = current
+            .entry(key.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        current = entry
+            .as_table_mut()
+            .ok_or_else(|| format!("{ENV_PREFIX}{path}: {key} is not a table"))?;
+    }
+    let value = format!("v = {raw}")
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()));
+    current.insert(last.clone(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(pairs: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn empty_config_uses_defaults() {
+        let config = ServerConfig::from_toml("", env(&[])).unwrap();
+        assert_eq!(config.server.bind, SocketAddr::from(([127, 0, 0, 1], 8080)));
+        assert_eq!(config.rules.pot, 200);
+        assert_eq!(config.rooms.command_queue, 64);
+        assert_eq!(config.robots.tick_millis, RobotParams::default().tick_millis);
+    }
+
+    #[test]
+    fn file_values_and_env_overrides() {
+        let text = r#"
+            [server]
+            bind = "0.0.0.0:9000"
+
+            [rules]
+            pot = 300
+            leftover_policy = "DealExtra"
+
+            [rules.deck]
+            common_count = 12
+            regular_count = 10
+            short_count = 8
+
+            [robots]
+            price_max = 25
+        "#;
+        let config = ServerConfig::from_toml(text, env(&[
+            ("FIGGIE__RULES__POT", "400"),
+            ("FIGGIE__ROOMS__MAX_ROOMS", "3"),
+            ("FIGGIE__SERVER__LOG_LEVEL", "debug"),
+            ("OTHER", "ignored"),
+        ])).unwrap();
+        assert_eq!(config.server.bind.port(), 9000);
+        assert_eq!(config.server.log_level, "debug");
+        assert_eq!(config.rules.pot, 400);
+        assert_eq!(config.rules.leftover_policy, LeftoverPolicy::DealExtra);
+        assert_eq!(config.rooms.max_rooms, 3);
+        assert_eq!(config.robots.price_max, 25);
+        assert_eq!(config.robots.price_min, RobotParams::default().price_min);
+    }
+
+    #[test]
+    fn cli_flags_win() {
+        let mut config = ServerConfig::from_toml("[server]\nlog_level = \"warn\"", env(&[])).unwrap();
+        let cli = Cli::parse_from([
+            "figgie-server", "--bind", "127.0.0.1:7000", "--log-level", "trace", "--bot-tokens", "a:bot1,a:bot2,b:bot3",
+        ]);
+        config.apply_cli(&cli);
+        assert_eq!(config.server.bind.port(), 7000);
+        assert_eq!(config.server.log_level, "trace");
+        assert_eq!(config.server.bot_tokens["a"], vec!["bot1", "bot2"]);
+        assert_eq!(config.server.bot_tokens["b"], vec!["bot3"]);
+        assert!(Cli::try_parse_from(["figgie-server", "--bot-tokens", "a"]).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        assert!(ServerConfig::from_toml("[rules]\npot = \"lots\"", env(&[])).is_err());
+        assert!(ServerConfig::from_toml("", env(&[("FIGGIE__ROOMS__MAX_ROOMS", "-1")])).is_err());
+        let half_tls = ServerConfig::from_toml("[server]\ntls_cert = \"cert.pem\"", env(&[])).unwrap();
+        assert!(half_tls.validate().is_err());
+        let robot_seat = ServerConfig::from_toml("[server.bot_tokens]\nsecret = [\"robot1\"]", env(&[])).unwrap();
+        assert!(robot_seat.validate().is_err());
+    }
+}
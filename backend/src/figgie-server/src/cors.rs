@@ -0,0 +1,65 @@
+use axum::http::{header, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+// 允许跨域访问 REST 接口的来源；为空时不加 CORS 头，只允许同源访问，"*" 表示任意来源
+pub fn layer(origins: &[String]) -> Option<CorsLayer> {
+    if origins.is_empty() {
+        return None;
+    }
+    let allow_origin = if origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        let parsed: Vec<HeaderValue> = origins
+            .iter()
+            .filter_map(|origin| match HeaderValue::from_str(origin.trim_end_matches('/')) {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    log::warn!("ignoring invalid CORS origin: {}", origin);
+                    None
+                }
+            })
+            .collect();
+        AllowOrigin::list(parsed)
+    };
+    // token 通过 Authorization 头传递，不需要带 cookie
+    Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+            .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    async fn preflight(origins: &[&str], origin: &str) -> Option<String> {
+        let origins: Vec<String> = origins.iter().map(|o| o.to_string()).collect();
+        let mut app = Router::new().route("/leaderboard", get(|| async { "ok" }));
+        if let Some(cors) = layer(&origins) {
+            app = app.layer(cors);
+        }
+        let request = Request::options("/leaderboard")
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .map(|v| v.to_str().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn allows_only_configured_origins() {
+        assert_eq!(preflight(&[], "https://evil.example").await, None);
+        let allowed = ["https://figgie.example/"];
+        assert_eq!(preflight(&allowed, "https://figgie.example").await.as_deref(), Some("https://figgie.example"));
+        assert_eq!(preflight(&allowed, "https://evil.example").await, None);
+        assert_eq!(preflight(&["*"], "https://any.example").await.as_deref(), Some("*"));
+    }
+}
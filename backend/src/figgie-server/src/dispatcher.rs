@@ -1,95 +1,847 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::{mpsc::*, Mutex};
-use log;
-use figgie_core::{Game, Action, Event};
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::{mpsc::*, oneshot, Mutex};
+use tokio::time::interval;
+use figgie_core::{Game, GameConfig, GameSnapshot, Action, Event, FiggieError, Phase, Player, SharedClock};
+use serde::{Deserialize, Serialize};
+use crate::outbox::*;
+use crate::snapshot::RoomSnapshot;
+use crate::store::Recorder;
+use crate::chat::{ChatError, ChatPolicy};
+use crate::metrics::{action_label, METRICS};
+use crate::robots::{robot_loop, RobotParams};
+use crate::limits::FloodLimits;
 
-pub type Dispatchers = Arc<Mutex<Vec<Arc<Mutex<Dispatcher>>>>>;
+pub type Dispatchers = Arc<Mutex<Vec<RoomHandle>>>;
 pub type HumanParticipants = Arc<Mutex<Vec<Participant>>>;
 
+// 检查掉线玩家是否需要机器人接管的间隔
+const TICK: Duration = Duration::from_secs(1);
+
+// action 被拒绝的原因：违反游戏规则，或者被服务端的聊天策略拦下
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ActionError {
+    Game(FiggieError),
+    Chat(ChatError),
+}
+
+impl ActionError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ActionError::Game(e) => e.code(),
+            ActionError::Chat(e) => e.code(),
+        }
+    }
+}
+
+impl std::fmt::Display for ActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActionError::Game(e) => e.fmt(f),
+            ActionError::Chat(e) => e.fmt(f),
+        }
+    }
+}
+
+impl From<FiggieError> for ActionError {
+    fn from(e: FiggieError) -> Self {
+        ActionError::Game(e)
+    }
+}
+
+impl From<ChatError> for ActionError {
+    fn from(e: ChatError) -> Self {
+        ActionError::Chat(e)
+    }
+}
+
+// 引擎处理完 action 后通过 reply 告知发送方结果，用于协议中的 Ack/Nack
+#[derive(Debug)]
+pub struct ActionRequest {
+    pub action: Action,
+    pub reply: Option<oneshot::Sender<Result<(), ActionError>>>,
+    // 由管理接口发起
+    pub by_admin: bool,
+}
+
+// dispatcher 的输入：玩家动作，以及连接层上报的在线状态变化
+#[derive(Debug)]
+pub enum Command {
+    Action(ActionRequest),
+    Connected(String),
+    Disconnected(String),
+    // 停机：通知所有参与者后退出，返回房间快照（游戏已结束时为 None）
+    Shutdown(oneshot::Sender<Option<RoomSnapshot>>),
+    // 以下为管理接口
+    Inspect(oneshot::Sender<RoomInfo>),
+    Dump(oneshot::Sender<GameSnapshot>),
+    // 换掉座位的出站队列，原来的连接随之断开；robot 为 false 时即踢出玩家
+    Reseat {
+        player_id: String,
+        outbox: OutboxSender,
+        robot: bool,
+        reply: oneshot::Sender<Result<Seat, FiggieError>>,
+    },
+}
+
+// 正在运行的房间；dispatcher 自己在任务里运行，外部只通过命令通道与它交互
+#[derive(Clone, Debug)]
+pub struct RoomHandle {
+    pub room_id: String,
+    pub room: Arc<GameConfig>,
+    pub commands: Sender<Command>,
+}
+
+// 管理接口看到的房间概况
+#[derive(Clone, Debug, Serialize)]
+pub struct RoomInfo {
+    pub room_id: String,
+    pub room_name: String,
+    pub game_id: Option<i64>,
+    pub phase: Phase,
+    pub round: u32,
+    pub paused: bool,
+    pub players: usize,
+    // 当前在线的真人玩家
+    pub connected: Vec<String>,
+    // 由机器人控制的座位，包括接管的真人座位
+    pub robots: Vec<String>,
+    pub kicked: Vec<String>,
+    pub elapsed_secs: u64,
+    pub round_elapsed_secs: u64,
+    // 距离上一条命令的秒数
+    pub idle_secs: u64,
+    pub last_activity_millis: u64,
+}
+
+// 换座后座位的当前状态，机器人接管时用它初始化
+#[derive(Debug)]
+pub struct Seat {
+    pub player: Player,
+    pub clock: SharedClock,
+}
+
+impl From<Action> for Command {
+    fn from(action: Action) -> Self {
+        Command::Action(ActionRequest {
+            action,
+            reply: None,
+            by_admin: false,
+        })
+    }
+}
+
+// 掉线超过 after 的真人座位交给机器人代打，玩家重新连上后交还
+#[derive(Clone, Debug)]
+pub struct Takeover {
+    pub after: Duration,
+    // 机器人通过这个通道把 action 发回本房间
+    pub commands: Sender<Command>,
+    pub params: RobotParams,
+    pub outbox_capacity: usize,
+}
+
+// 玩家掉线后如何处理其挂单
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum AbsentQuotePolicy {
+    // 保留挂单，其他玩家仍可成交
+    #[default]
+    Keep,
+    // 掉线时撤掉该玩家的全部挂单
+    CancelOnDisconnect,
+}
+
 #[derive(Debug)]
 pub struct Participant {
     pub player_id: String,
-    pub action_sender: Sender<Action>,
-    pub event_receiver: Receiver<Event>,
+    // 房间配置，连接建立时通过 Hello 发给客户端
+    pub room: Arc<GameConfig>,
+    pub action_sender: Sender<Command>,
+    pub event_receiver: OutboxReceiver,
+    // 连接层对这个座位的入站限制
+    pub limits: FloodLimits,
+    // 房间创建者的座位，可以从连接上暂停、恢复和结束对局
+    pub controls: bool,
 }
 
 pub fn create_participant(
     player_id: String,
-    dispatcher_sender: Sender<Action>,
-) -> (Participant, Sender<Event>) {
-    let (event_sender, event_receiver) = channel(32);
+    room: Arc<GameConfig>,
+    dispatcher_sender: Sender<Command>,
+    outbox_capacity: usize,
+) -> (Participant, OutboxSender) {
+    let (event_sender, event_receiver) = outbox(outbox_capacity);
 
     let participant = Participant {
-        player_id: player_id,
+        player_id,
+        room,
         action_sender: dispatcher_sender,
-        event_receiver: event_receiver,
+        event_receiver,
+        limits: FloodLimits::default(),
+        controls: false,
     };
 
     (participant, event_sender)
 }
 
+// 从等待队列中取出座位，同一座位同时只能有一个连接
+pub async fn take_participant(pool: &HumanParticipants, room_id: &str, player_id: &str) -> Option<Participant> {
+    let mut vec = pool.lock().await;
+    // 房间已结束或座位已被换掉的条目直接清理
+    vec.retain(|p| !p.event_receiver.is_closed());
+    let idx = vec
+        .iter()
+        .position(|p| p.player_id == player_id && p.room.room_id == room_id)?;
+    Some(vec.remove(idx))
+}
+
+// 座位所在房间的入站限制，连接握手前用来设置帧大小上限
+pub async fn seat_limits(pool: &HumanParticipants, room_id: &str, player_id: &str) -> Option<FloodLimits> {
+    pool.lock()
+        .await
+        .iter()
+        .find(|p| p.player_id == player_id && p.room.room_id == room_id)
+        .map(|p| p.limits)
+}
+
 #[derive(Debug)]
 pub struct Dispatcher {
     pub room_id: String,
     pub game: Game,
     // dispatcher 接收所有参与者发来的 action
-    pub receiver: Receiver<Action>,
-    // dispatcher 给每个参与者发 event
-    pub participants: HashMap<String, Sender<Event>>,
-    pub last_activity: Instant,
+    pub receiver: Receiver<Command>,
+    // dispatcher 给每个参与者发 event，每人一个出站队列
+    pub participants: HashMap<String, OutboxSender>,
+    // 当前在线的真人玩家
+    pub connected: HashSet<String>,
+    // 曾经连上、当前掉线的玩家及掉线时间
+    pub disconnected: HashMap<String, Duration>,
+    // 机器人控制的座位，不处理它们的在线状态
+    pub robot_seats: HashSet<String>,
+    // 被管理员踢出或因刷屏被踢出的玩家
+    pub kicked: HashSet<String>,
+    // 代打掉线玩家的机器人的出站队列；移除即让机器人退出
+    pub stand_ins: HashMap<String, OutboxSender>,
+    pub takeover: Option<Takeover>,
+    pub absent_quote_policy: AbsentQuotePolicy,
+    pub flood_limits: FloodLimits,
+    // 开房的玩家；匹配队列开的房间没有
+    pub creator: Option<String>,
+    // 成交和结算写入数据库
+    pub recorder: Option<Recorder>,
+    pub chat: ChatPolicy,
+    // 与 game 共用同一个时钟
+    pub clock: SharedClock,
+    pub opened_at: Duration,
+    pub last_activity: Duration,
 }
 
 impl Dispatcher {
+    pub fn new(room_id: String, game: Game, receiver: Receiver<Command>) -> Self {
+        let clock = game.clock().clone();
+        Dispatcher {
+            room_id,
+            game,
+            receiver,
+            participants: HashMap::new(),
+            connected: HashSet::new(),
+            disconnected: HashMap::new(),
+            robot_seats: HashSet::new(),
+            kicked: HashSet::new(),
+            stand_ins: HashMap::new(),
+            takeover: None,
+            absent_quote_policy: AbsentQuotePolicy::default(),
+            flood_limits: FloodLimits::default(),
+            creator: None,
+            recorder: None,
+            chat: ChatPolicy::default(),
+            opened_at: clock.now(),
+            last_activity: clock.now(),
+            clock,
+        }
+    }
+
     pub fn register(
         &mut self,
         player_id: String,
-        event_sender: Sender<Event>,
+        event_sender: OutboxSender,
     ) {
+        if player_id.starts_with("robot") {
+            self.robot_seats.insert(player_id.clone());
+        }
         self.participants.insert(player_id, event_sender);
-        self.last_activity = Instant::now();
+        self.last_activity = self.clock.now();
     }
 
     pub async fn run(&mut self) {
-        println!("Dispatcher for room {} started", self.room_id);
-
-        while let Some(action) = self.receiver.recv().await {
-            self.last_activity = Instant::now();
-            let events = self.game.handle_action(action);
-            self.handover_events(events.clone()).await;
-            if !events.is_empty() {
-                match events[0] {
-                    Event::GameEnded{..} => break,
-                    _ => {},
+        log::info!("dispatcher for room {} started", self.room_id);
+
+        let mut ticker = interval(TICK);
+        loop {
+            let command = select! {
+                command = self.receiver.recv() => {
+                    let Some(command) = command else { break };
+                    command
+                }
+                _ = ticker.tick() => {
+                    self.take_over_absent();
+                    let events = self.end_expired_round();
+                    if !events.is_empty() {
+                        self.publish(events);
+                    }
+                    continue;
+                }
+            };
+            self.last_activity = self.clock.now();
+            let events = match command {
+                Command::Action(request) => self.handle_request(request),
+                Command::Connected(player_id) => self.on_connected(player_id),
+                Command::Disconnected(player_id) => self.on_disconnected(player_id),
+                Command::Shutdown(reply) => {
+                    self.shutdown(reply);
+                    break;
+                }
+                Command::Inspect(reply) => {
+                    let _ = reply.send(self.info());
+                    vec![]
+                }
+                Command::Dump(reply) => {
+                    let _ = reply.send(self.game.snapshot());
+                    vec![]
+                }
+                Command::Reseat { player_id, outbox, robot, reply } => {
+                    let (result, events) = self.reseat(player_id, outbox, robot);
+                    let _ = reply.send(result);
+                    events
+                }
+            };
+            let game_ended = matches!(events.first(), Some(Event::GameEnded { .. }));
+            self.publish(events);
+            if game_ended {
+                break;
+            }
+        }
+
+        METRICS.forget_room(&self.room_id);
+        log::info!("dispatcher for room {} stopped", self.room_id);
+    }
+
+    // 写库后分发给各个座位
+    fn publish(&mut self, events: Vec<Event>) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(self.game.round, self.clock.now().as_millis() as u64, &events);
+        }
+        self.handover_events(events);
+        self.report_queues();
+    }
+
+    // 回合时钟以服务端为准：交易时间走完就由 dispatcher 结算，不依赖客户端发 EndRound
+    pub fn end_expired_round(&mut self) -> Vec<Event> {
+        if self.game.phase != Phase::Trading || !self.game.round_time_remaining().is_zero() {
+            return vec![];
+        }
+        match self.game.end_round() {
+            Ok(events) => {
+                log::info!("round time is up, room id: {}, round: {}", self.room_id, self.game.round);
+                events
+            }
+            Err(e) => {
+                log::error!("failed to end expired round, room id: {}, reason: {}", self.room_id, e);
+                vec![]
+            }
+        }
+    }
+
+    fn shutdown(&mut self, reply: oneshot::Sender<Option<RoomSnapshot>>) {
+        let snapshot = (self.game.phase != Phase::GameOver).then(|| RoomSnapshot {
+            room_id: self.room_id.clone(),
+            game_id: self.recorder.as_ref().map(|r| r.game_id),
+            absent_quote_policy: self.absent_quote_policy,
+            limits: self.flood_limits,
+            creator: self.creator.clone(),
+            game: self.game.snapshot(),
+        });
+        log::info!("room {} shutting down, restorable: {}", self.room_id, snapshot.is_some());
+        self.handover_events(vec![Event::ServerShutdown { restorable: snapshot.is_some() }]);
+        let _ = reply.send(snapshot);
+    }
+
+    fn info(&self) -> RoomInfo {
+        let now = self.clock.now();
+        let sorted = |set: &HashSet<String>| {
+            let mut ids: Vec<String> = set.iter().cloned().collect();
+            ids.sort();
+            ids
+        };
+        RoomInfo {
+            room_id: self.room_id.clone(),
+            room_name: self.game.game_config.room_name.clone(),
+            game_id: self.recorder.as_ref().map(|r| r.game_id),
+            phase: self.game.phase,
+            round: self.game.round,
+            paused: self.game.is_paused(),
+            players: self.game.game_config.players.len(),
+            connected: sorted(&self.connected),
+            robots: sorted(&self.robot_seats.iter().chain(self.stand_ins.keys()).cloned().collect()),
+            kicked: sorted(&self.kicked),
+            elapsed_secs: now.saturating_sub(self.opened_at).as_secs(),
+            round_elapsed_secs: self.game.round_elapsed().as_secs(),
+            idle_secs: now.saturating_sub(self.last_activity).as_secs(),
+            last_activity_millis: self.last_activity.as_millis() as u64,
+        }
+    }
+
+    fn reseat(&mut self, player_id: String, outbox: OutboxSender, robot: bool) -> (Result<Seat, FiggieError>, Vec<Event>) {
+        let player = match self.game.player(&player_id) {
+            Ok(player) => player.clone(),
+            Err(e) => return (Err(e), vec![]),
+        };
+        // 旧的发送端被释放，对应连接的 recv 返回 None 后断开
+        self.participants.insert(player_id.clone(), outbox);
+        self.stand_ins.remove(&player_id);
+        let events = if robot {
+            log::info!("seat handed to robot, room id: {}, player id: {}", self.room_id, player_id);
+            if let Some(event) = self.pause_notice() {
+                self.participants[&player_id].push(event, true);
+            }
+            self.connected.remove(&player_id);
+            self.disconnected.remove(&player_id);
+            self.kicked.remove(&player_id);
+            self.robot_seats.insert(player_id);
+            vec![]
+        } else {
+            log::info!("player kicked, room id: {}, player id: {}", self.room_id, player_id);
+            self.robot_seats.remove(&player_id);
+            self.kicked.insert(player_id.clone());
+            // 被踢的玩家不会再回来，报价不能留在盘口上；座位立即交给机器人
+            let mut events = self.game.cancel_player_quotes(&player_id);
+            events.extend(self.on_disconnected(player_id.clone()));
+            self.stand_in(&player_id);
+            events
+        };
+        (Ok(Seat { player, clock: self.clock.clone() }), events)
+    }
+
+    fn handle_request(&mut self, request: ActionRequest) -> Vec<Event> {
+        let ActionRequest { mut action, reply, by_admin } = request;
+        let label = action_label(&action);
+        METRICS.actions.with_label_values(&[label]).inc();
+        let result = self.screen_chat(&mut action).map_err(ActionError::from).and_then(|()| {
+            let _timer = METRICS.match_latency.with_label_values(&[label]).start_timer();
+            self.game.handle_action(action.clone()).map_err(ActionError::from)
+        });
+        if let Err(e) = &result {
+            METRICS.rejected(e);
+            log::warn!(
+                "action rejected, room id: {}, player id: {}, reason: {}",
+                self.room_id, action.player_id().unwrap_or("-"), e
+            );
+        }
+        let (mut events, outcome) = match result {
+            Ok(events) => (events, Ok(())),
+            Err(e) => (vec![], Err(e)),
+        };
+        // 管理员结束的对局视同打满，照常计分
+        if by_admin {
+            for event in &mut events {
+                if let Event::GameEnded { completed, .. } = event {
+                    *completed = true;
                 }
             }
         }
+        if let Some(reply) = reply {
+            let _ = reply.send(outcome);
+        }
+        events
+    }
 
-        println!("Dispatcher for room {} stopped", self.room_id);
+    // 聊天先经过长度、限流和内容过滤，文本替换为过滤后的版本
+    fn screen_chat(&mut self, action: &mut Action) -> Result<(), ChatError> {
+        if let Action::Chat { player_id, text, .. } = action {
+            *text = self.chat.check(player_id, text, self.clock.now())?;
+        }
+        Ok(())
     }
 
-    pub async fn handover_events(&self, events: Vec<Event>) {
+    fn on_connected(&mut self, player_id: String) -> Vec<Event> {
+        if self.robot_seats.contains(&player_id) {
+            return vec![];
+        }
+        log::info!("player connected, room id: {}, player id: {}", self.room_id, player_id);
+        self.connected.insert(player_id.clone());
+        if self.stand_ins.remove(&player_id).is_some() {
+            log::info!("seat handed back from robot, room id: {}, player id: {}", self.room_id, player_id);
+        }
+        if self.disconnected.remove(&player_id).is_some() {
+            vec![Event::PlayerReconnected { player_id }]
+        } else {
+            vec![]
+        }
+    }
+
+    fn on_disconnected(&mut self, player_id: String) -> Vec<Event> {
+        // 被换座的旧连接随后也会上报断开，忽略重复的
+        if self.robot_seats.contains(&player_id) || self.disconnected.contains_key(&player_id) {
+            return vec![];
+        }
+        log::info!("player disconnected, room id: {}, player id: {}", self.room_id, player_id);
+        self.connected.remove(&player_id);
+        let mut events = vec![];
+        if self.absent_quote_policy == AbsentQuotePolicy::CancelOnDisconnect {
+            events.extend(self.game.cancel_player_quotes(&player_id));
+        }
+        events.push(Event::PlayerDisconnected { player_id: player_id.clone() });
+        self.disconnected.insert(player_id, self.clock.now());
+        events
+    }
+
+    // 掉线超时的座位交给机器人
+    pub fn take_over_absent(&mut self) {
+        let Some(takeover) = self.takeover.clone() else {
+            return;
+        };
+        if self.game.phase == Phase::GameOver {
+            return;
+        }
+        let now = self.clock.now();
+        let mut absent: Vec<String> = self
+            .disconnected
+            .iter()
+            .filter(|(id, since)| {
+                now.saturating_sub(**since) >= takeover.after
+                    && !self.kicked.contains(*id)
+                    && !self.stand_ins.contains_key(*id)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        absent.sort();
+
+        for player_id in absent {
+            self.stand_in(&player_id);
+        }
+    }
+
+    // 用座位当前的手牌和现金启动一个代打机器人；没有开启代打时什么也不做
+    fn stand_in(&mut self, player_id: &str) {
+        let Some(takeover) = self.takeover.clone() else {
+            return;
+        };
+        if self.game.phase == Phase::GameOver {
+            return;
+        }
+        let Ok(player) = self.game.player(player_id) else {
+            return;
+        };
+        let (hand, cash) = (player.hand.clone(), player.cash);
+        let (participant, outbox) = create_participant(
+            player_id.to_string(),
+            Arc::new(self.game.game_config.clone()),
+            takeover.commands.clone(),
+            takeover.outbox_capacity,
+        );
+        log::info!("seat taken over by robot, room id: {}, player id: {}", self.room_id, player_id);
+        if let Some(event) = self.pause_notice() {
+            outbox.push(event, true);
+        }
+        self.stand_ins.insert(player_id.to_string(), outbox);
+        let clock = self.clock.clone();
+        tokio::spawn(robot_loop(participant, hand, cash, clock, takeover.params));
+    }
+
+    // 暂停期间接手座位的机器人没收到过 TradingPaused，先补发一条
+    fn pause_notice(&self) -> Option<Event> {
+        let paused_at = self.game.paused_at?;
+        Some(Event::TradingPaused {
+            round_id: self.game.round as u8,
+            server_time: paused_at.as_secs(),
+            remaining_millis: self.game.round_time_remaining().as_millis() as u64,
+        })
+    }
+
+    // 只往队列里放，不等待任何参与者
+    pub fn handover_events(&self, events: Vec<Event>) {
+        let _timer = METRICS.fanout_latency.start_timer();
         for event in events {
             match event.target_player() {
                 // 🎯 定向发送
                 Some(player_id) => {
-                    if let Some(tx) = self.participants.get(player_id) {
-                        let _ = tx.send(event).await;
-                    } else {
-                        log::warn!(
-                            "target participant not found, room id: {}, player id: {}", self.room_id, player_id
-                        );
+                    let mut recipients = vec![player_id.to_string()];
+                    recipients.extend(event.copy_to().cloned());
+                    for player_id in recipients {
+                        if let Some(tx) = self.participants.get(&player_id) {
+                            self.deliver(&player_id, tx, event.clone());
+                        } else {
+                            log::warn!(
+                                "target participant not found, room id: {}, player id: {}", self.room_id, player_id
+                            );
+                        }
                     }
                 }
 
                 // 📢 群发
                 None => {
-                    for tx in self.participants.values() {
-                        let _ = tx.send(event.clone()).await;
+                    for (player_id, tx) in &self.participants {
+                        self.deliver(player_id, tx, event.clone());
                     }
                 }
             }
         }
     }
+
+    fn deliver(&self, player_id: &str, tx: &OutboxSender, event: Event) {
+        // 掉线玩家的队列满了只丢事件；在线玩家积压过多则断开，由连接层上报 Disconnected
+        let online = !self.disconnected.contains_key(player_id);
+        // 代打的机器人收到与座位相同的事件
+        if let Some(robot) = self.stand_ins.get(player_id) {
+            robot.push(event.clone(), true);
+        }
+        match tx.push(event, online) {
+            Push::Queued => {}
+            Push::Coalesced => METRICS.outbox_coalesced.with_label_values(&[&self.room_id]).inc(),
+            Push::Dropped => METRICS.outbox_dropped.with_label_values(&[&self.room_id]).inc(),
+            Push::Overflowed => {
+                METRICS.outbox_overflows.with_label_values(&[&self.room_id]).inc();
+                log::warn!(
+                    "slow consumer disconnected, room id: {}, player id: {}, capacity: {}",
+                    self.room_id, player_id, tx.capacity()
+                );
+            }
+        }
+    }
+
+    // 更新房间的队列深度指标，积压过半时记日志
+    fn report_queues(&self) {
+        let queue_stats = self.queue_stats();
+        let depth: usize = queue_stats.values().map(|stats| stats.depth).sum();
+        METRICS.outbox_depth.with_label_values(&[&self.room_id]).set(depth as i64);
+        for (player_id, stats) in queue_stats {
+            if stats.depth >= stats.capacity / 2 {
+                log::warn!(
+                    "outbound queue backlog, room id: {}, player id: {}, stats: {:?}",
+                    self.room_id, player_id, stats
+                );
+            }
+        }
+    }
+
+    // 各参与者出站队列的积压情况
+    pub fn queue_stats(&self) -> HashMap<String, OutboxStats> {
+        self.participants
+            .iter()
+            .map(|(player_id, tx)| (player_id.clone(), tx.stats()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::config;
+    use figgie_core::*;
+    use tokio::time::timeout;
+
+    #[tokio::test]
+    async fn robot_stands_in_for_absent_player_until_reconnect() {
+        let config = config("R1");
+        let clock = Arc::new(ManualClock::new(Duration::from_secs(1_000)));
+        let mut game = Game::with_clock(config, clock.clone()).unwrap();
+        game.start_round(1).unwrap();
+        let room = Arc::new(game.game_config.clone());
+        let (tx, rx) = channel(16);
+        let mut dispatcher = Dispatcher::new("R1".to_string(), game, rx);
+        let mut seats = vec![];
+        for info in room.players.iter() {
+            let (participant, outbox) = create_participant(info.id.clone(), room.clone(), tx.clone(), 16);
+            dispatcher.register(info.id.clone(), outbox);
+            seats.push(participant);
+        }
+        dispatcher.takeover = Some(Takeover {
+            after: Duration::from_secs(30),
+            commands: tx.clone(),
+            params: RobotParams { delay_min_secs: 0, delay_max_secs: 0, tick_millis: 10, ..RobotParams::default() },
+            outbox_capacity: 16,
+        });
+
+        dispatcher.on_connected("p0".to_string());
+        dispatcher.on_disconnected("p0".to_string());
+        clock.advance(Duration::from_secs(29));
+        dispatcher.take_over_absent();
+        assert!(dispatcher.stand_ins.is_empty());
+
+        // 超时后由机器人用这个座位下单
+        clock.advance(Duration::from_secs(1));
+        dispatcher.take_over_absent();
+        assert_eq!(dispatcher.info().robots, vec!["p0"]);
+        let command = timeout(Duration::from_secs(2), dispatcher.receiver.recv()).await.unwrap();
+        assert!(matches!(command, Some(Command::Action(ActionRequest { action: Action::PlaceQuote(q), .. })) if q.player_id == "p0"));
+
+        // 玩家回来后机器人退出，原来的出站队列仍然可用
+        let events = dispatcher.on_connected("p0".to_string());
+        assert!(matches!(&events[..], [Event::PlayerReconnected { .. }]));
+        assert!(dispatcher.stand_ins.is_empty());
+        assert!(!seats[0].event_receiver.is_closed());
+        clock.advance(Duration::from_secs(60));
+        dispatcher.take_over_absent();
+        assert!(dispatcher.stand_ins.is_empty());
+    }
+
+    #[tokio::test]
+    async fn kicked_seat_drops_its_quotes_and_goes_to_a_robot() {
+        let clock = Arc::new(ManualClock::new(Duration::from_secs(1_000)));
+        let mut game = Game::with_clock(config("R-KICK"), clock.clone()).unwrap();
+        game.start_round(1).unwrap();
+        let room = Arc::new(game.game_config.clone());
+        let (tx, rx) = channel(16);
+        let mut dispatcher = Dispatcher::new("R-KICK".to_string(), game, rx);
+        let mut seats = vec![];
+        for info in room.players.iter() {
+            let (participant, outbox) = create_participant(info.id.clone(), room.clone(), tx.clone(), 16);
+            dispatcher.register(info.id.clone(), outbox);
+            seats.push(participant);
+        }
+        dispatcher.takeover = Some(Takeover {
+            after: Duration::from_secs(30),
+            commands: tx.clone(),
+            params: RobotParams { delay_min_secs: 0, delay_max_secs: 0, tick_millis: 10, ..RobotParams::default() },
+            outbox_capacity: 16,
+        });
+        dispatcher.on_connected("p0".to_string());
+        let quote = Quote { player_id: "p0".to_string(), suit: Suit::Heart, side: Side::Bid, price: 5 };
+        dispatcher.game.handle_action(Action::PlaceQuote(quote)).unwrap();
+
+        // 默认策略掉线不撤单，但被踢的座位一定撤单，并且不等超时就由机器人接手
+        let (_, outbox) = create_participant("p0".to_string(), room.clone(), tx.clone(), 1);
+        let (seat, events) = dispatcher.reseat("p0".to_string(), outbox, false);
+        assert!(seat.is_ok());
+        assert!(matches!(&events[..], [Event::QuoteCanceled { .. }, Event::PlayerDisconnected { .. }]));
+        assert!(dispatcher.game.state.quotes.iter().all(|q| q.player_id != "p0"));
+        assert_eq!(dispatcher.info().kicked, vec!["p0"]);
+        assert_eq!(dispatcher.info().robots, vec!["p0"]);
+        let command = timeout(Duration::from_secs(2), dispatcher.receiver.recv()).await.unwrap();
+        assert!(matches!(command, Some(Command::Action(ActionRequest { action: Action::PlaceQuote(q), .. })) if q.player_id == "p0"));
+    }
+
+    #[tokio::test]
+    async fn robot_seated_during_a_pause_waits_for_resume() {
+        let clock = Arc::new(ManualClock::new(Duration::from_secs(1_000)));
+        let mut game = Game::with_clock(config("R-PAUSE"), clock.clone()).unwrap();
+        game.start_round(1).unwrap();
+        game.pause().unwrap();
+        let room = Arc::new(game.game_config.clone());
+        let (tx, rx) = channel(16);
+        let mut dispatcher = Dispatcher::new("R-PAUSE".to_string(), game, rx);
+        let mut seats = vec![];
+        for info in room.players.iter() {
+            let (participant, outbox) = create_participant(info.id.clone(), room.clone(), tx.clone(), 16);
+            dispatcher.register(info.id.clone(), outbox);
+            seats.push(participant);
+        }
+        dispatcher.takeover = Some(Takeover {
+            after: Duration::from_secs(30),
+            commands: tx.clone(),
+            params: RobotParams { delay_min_secs: 0, delay_max_secs: 0, tick_millis: 10, ..RobotParams::default() },
+            outbox_capacity: 16,
+        });
+
+        // 代打的机器人一上来就知道在暂停，不会下单
+        dispatcher.on_connected("p0".to_string());
+        dispatcher.on_disconnected("p0".to_string());
+        clock.advance(Duration::from_secs(30));
+        dispatcher.take_over_absent();
+        assert_eq!(dispatcher.info().robots, vec!["p0"]);
+        assert!(timeout(Duration::from_millis(300), dispatcher.receiver.recv()).await.is_err());
+
+        let events = dispatcher.game.resume().unwrap();
+        dispatcher.handover_events(events);
+        // 暂停时机器人把下次行动推后 pause_secs，手动时钟要跟着走
+        let mut command = None;
+        for _ in 0..20 {
+            clock.advance(Duration::from_secs(RobotParams::default().pause_secs));
+            if let Ok(received) = timeout(Duration::from_millis(100), dispatcher.receiver.recv()).await {
+                command = received;
+                break;
+            }
+        }
+        assert!(matches!(command, Some(Command::Action(ActionRequest { action: Action::PlaceQuote(q), .. })) if q.player_id == "p0"));
+
+        // 管理员换上的机器人同样先收到暂停
+        dispatcher.game.pause().unwrap();
+        let (mut robot, outbox) = create_participant("p1".to_string(), room.clone(), tx.clone(), 16);
+        let (seat, _) = dispatcher.reseat("p1".to_string(), outbox, true);
+        assert!(seat.is_ok());
+        assert!(matches!(robot.event_receiver.recv().await, Some(Event::TradingPaused { round_id: 1, .. })));
+    }
+
+    #[test]
+    fn queue_metrics_are_exported_per_room() {
+        let game = Game::new(config("R-QUEUE")).unwrap();
+        let room = Arc::new(game.game_config.clone());
+        let (tx, rx) = channel(16);
+        let mut dispatcher = Dispatcher::new("R-QUEUE".to_string(), game, rx);
+        let mut seats = vec![];
+        for info in room.players.iter() {
+            let (participant, outbox) = create_participant(info.id.clone(), room.clone(), tx.clone(), 4);
+            dispatcher.register(info.id.clone(), outbox);
+            seats.push(participant);
+        }
+        dispatcher.on_connected("p0".to_string());
+        dispatcher.on_disconnected("p0".to_string());
+
+        let paused = || Event::TradingPaused { round_id: 1, server_time: 0, remaining_millis: 0 };
+        let quote = || Event::QuotePlaced {
+            quote: Quote { player_id: "p1".to_string(), suit: Suit::Heart, side: Side::Bid, price: 5 },
+        };
+        // 积压过半后同一盘口只保留最新的；队列满后掉线玩家丢事件，在线玩家被断开
+        dispatcher.handover_events(vec![paused(), paused(), quote(), quote(), paused(), paused()]);
+        dispatcher.report_queues();
+
+        let text = METRICS.render();
+        assert!(text.contains("figgie_outbox_depth{room=\"R-QUEUE\"} 4"));
+        assert!(text.contains("figgie_outbox_coalesced_total{room=\"R-QUEUE\"} 4"));
+        assert!(text.contains("figgie_outbox_dropped_total{room=\"R-QUEUE\"} 1"));
+        assert!(text.contains("figgie_outbox_overflows_total{room=\"R-QUEUE\"} 3"));
+
+        METRICS.forget_room("R-QUEUE");
+        assert!(!METRICS.render().contains("R-QUEUE"));
+    }
+
+    // 交易时间走完由 dispatcher 结算回合；暂停期间计时不走
+    #[test]
+    fn ends_rounds_when_time_is_up() {
+        let clock = Arc::new(ManualClock::new(Duration::from_secs(1_000)));
+        let mut game = Game::with_clock(config("R-CLOCK"), clock.clone()).unwrap();
+        game.start_round(1).unwrap();
+        let (_tx, rx) = channel(4);
+        let mut dispatcher = Dispatcher::new("R-CLOCK".to_string(), game, rx);
+
+        clock.advance(Duration::from_secs(100));
+        dispatcher.game.pause().unwrap();
+        clock.advance(Duration::from_secs(500));
+        assert!(dispatcher.end_expired_round().is_empty());
+
+        dispatcher.game.resume().unwrap();
+        clock.advance(Duration::from_secs(139));
+        assert!(dispatcher.end_expired_round().is_empty());
+        clock.advance(Duration::from_secs(1));
+        assert!(matches!(&dispatcher.end_expired_round()[..], [Event::RoundEnded { round_id: 1, .. }]));
+        assert_eq!(dispatcher.game.phase, Phase::RoundOver);
+        assert!(dispatcher.end_expired_round().is_empty());
+    }
+
+    #[tokio::test]
+    async fn seat_limits_follow_the_room() {
+        let room = Arc::new(config("R-LIMITS"));
+        let (tx, _rx) = channel(4);
+        let (mut participant, _outbox) = create_participant("p0".to_string(), room, tx, 4);
+        participant.limits.max_message_bytes = 512;
+        let pool: HumanParticipants = Arc::new(Mutex::new(vec![participant]));
+
+        let limits = seat_limits(&pool, "R-LIMITS", "p0").await.unwrap();
+        assert_eq!(limits.max_message_bytes, 512);
+        assert!(seat_limits(&pool, "R-LIMITS", "p1").await.is_none());
+        // 只是查看，座位仍然可以接入
+        assert!(take_participant(&pool, "R-LIMITS", "p0").await.is_some());
+    }
 }
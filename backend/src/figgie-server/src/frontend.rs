@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use tower::ServiceExt;
+use tower_http::services::{ServeDir, ServeFile};
+
+// vite 构建产物中带内容哈希的文件都在这个目录下
+const ASSETS_PREFIX: &str = "/assets/";
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+// index.html 等入口每次都要向服务器确认
+const NO_CACHE: &str = "no-cache";
+
+// 托管前端构建目录；找不到的页面路径交给 index.html，由前端路由处理
+pub fn service(dir: &Path) -> Router {
+    let index = Arc::new(dir.join("index.html"));
+    let spa = get(index_or_not_found).with_state(index);
+    Router::new()
+        .fallback_service(ServeDir::new(dir).fallback(spa))
+        .layer(middleware::from_fn(cache_headers))
+}
+
+// 启动时检查目录，缺少 index.html 时只警告
+pub fn check(dir: &Path) {
+    if !dir.join("index.html").is_file() {
+        log::warn!("static_dir {} has no index.html, run `npm run build` in frontend/", dir.display());
+    }
+}
+
+// 看起来像文件的路径（最后一段带扩展名）不回退，避免把 HTML 当成脚本缓存
+async fn index_or_not_found(State(index): State<Arc<PathBuf>>, request: Request) -> Response {
+    let last = request.uri().path().rsplit('/').next().unwrap_or_default();
+    if last.contains('.') {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    match ServeFile::new(index.as_path()).oneshot(request).await {
+        Ok(response) => response.into_response(),
+        Err(never) => match never {},
+    }
+}
+
+async fn cache_headers(request: Request, next: Next) -> Response {
+    let immutable = request.uri().path().starts_with(ASSETS_PREFIX);
+    let mut response = next.run(request).await;
+    if response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED {
+        let value = if immutable { IMMUTABLE } else { NO_CACHE };
+        response.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static(value));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+
+    async fn fetch(app: &Router, path: &str) -> (StatusCode, Option<String>, String) {
+        let response = app.clone().oneshot(Request::get(path).body(Body::empty()).unwrap()).await.unwrap();
+        let status = response.status();
+        let cache = response
+            .headers()
+            .get(header::CACHE_CONTROL)
+            .map(|v| v.to_str().unwrap().to_string());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, cache, String::from_utf8_lossy(&body).to_string())
+    }
+
+    #[tokio::test]
+    async fn serves_assets_with_spa_fallback() {
+        let dir = std::env::temp_dir().join(format!("figgie-frontend-{}", rand::random::<u32>()));
+        std::fs::create_dir_all(dir.join("assets")).unwrap();
+        std::fs::write(dir.join("index.html"), "<div id=root></div>").unwrap();
+        std::fs::write(dir.join("assets/app-1a2b.js"), "console.log(1)").unwrap();
+        let app = service(&dir);
+
+        let (status, cache, body) = fetch(&app, "/assets/app-1a2b.js").await;
+        assert_eq!((status, cache.as_deref(), body.as_str()), (StatusCode::OK, Some(IMMUTABLE), "console.log(1)"));
+
+        let (status, cache, body) = fetch(&app, "/").await;
+        assert_eq!((status, cache.as_deref()), (StatusCode::OK, Some(NO_CACHE)));
+        assert!(body.contains("root"));
+
+        // 前端路由回退到 index.html，缺失的静态文件仍是 404
+        let (status, _, body) = fetch(&app, "/game").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("root"));
+        let (status, cache, _) = fetch(&app, "/assets/missing.js").await;
+        assert_eq!((status, cache), (StatusCode::NOT_FOUND, None));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,305 @@
+use crate::dispatcher::*;
+use crate::adapter::*;
+use crate::protocol::*;
+use crate::metrics::ConnectionGuard;
+use crate::limits::{self, FloodGuard, Violation};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde::Deserialize;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::select;
+use tokio::sync::mpsc::channel;
+use tokio::time::Instant;
+
+// 外部机器人进程的 TCP 接入：每行一个 JSON，消息格式与 WebSocket 协议相同
+// 连接后的第一行必须是登录消息：{"token": "...", "room_id": "...", "player_id": "..."}
+#[derive(Debug, Deserialize)]
+struct BotLogin {
+    token: String,
+    room_id: String,
+    player_id: String,
+}
+
+// 登录消息的长度上限，此时还不知道座位的限流配置
+const MAX_LOGIN_BYTES: usize = 1024;
+
+// token → 持有该 token 的机器人可以使用的座位
+pub type BotTokens = Arc<HashMap<String, Vec<String>>>;
+
+pub async fn serve_bots(listener: TcpListener, tokens: BotTokens, human_participants: HumanParticipants) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::warn!("bot gateway accept failed: {}", e);
+                continue;
+            }
+        };
+        log::info!("bot connected from {}", peer);
+        let tokens = tokens.clone();
+        let human_participants = human_participants.clone();
+        tokio::spawn(async move {
+            handle_bot(stream, tokens, human_participants).await;
+            log::info!("bot from {} disconnected", peer);
+        });
+    }
+}
+
+async fn write_line<W: AsyncWrite + Unpin>(writer: &mut W, message: ServerMessage) -> std::io::Result<()> {
+    let mut text = server_message_to_text(&message).map_err(std::io::Error::other)?;
+    text.push('\n');
+    writer.write_all(text.as_bytes()).await
+}
+
+// 读取一行，不含换行符；超过 max 字节的行不会整行缓存，读到的部分直接丢弃，只返回它的长度
+enum Line {
+    Text(String),
+    TooLong(usize),
+}
+
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R, max: usize) -> std::io::Result<Option<Line>> {
+    let mut buf = Vec::new();
+    // 多留两个字节给 \r\n
+    let limit = max as u64 + 2;
+    let n = (&mut *reader).take(limit).read_until(b'\n', &mut buf).await?;
+    if n == 0 {
+        return Ok(None);
+    }
+    // 读到换行符，或者没读满就到了连接末尾，这一行就结束了
+    let mut complete = buf.last() == Some(&b'\n') || (n as u64) < limit;
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+        if buf.last() == Some(&b'\r') {
+            buf.pop();
+        }
+    }
+    if complete && buf.len() <= max {
+        return Ok(Some(Line::Text(String::from_utf8_lossy(&buf).into_owned())));
+    }
+    let mut size = buf.len();
+    while !complete {
+        buf.clear();
+        let n = (&mut *reader).take(limit).read_until(b'\n', &mut buf).await?;
+        complete = n == 0 || buf.last() == Some(&b'\n');
+        size += buf.strip_suffix(b"\n").unwrap_or(&buf).len();
+    }
+    Ok(Some(Line::TooLong(size)))
+}
+
+async fn handle_bot(stream: TcpStream, tokens: BotTokens, human_participants: HumanParticipants) {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    let login = match read_line(&mut reader, MAX_LOGIN_BYTES).await {
+        Ok(Some(Line::Text(line))) => serde_json::from_str::<BotLogin>(&line).map_err(|e| e.to_string()),
+        Ok(Some(Line::TooLong(size))) => Err(format!("login is {size} bytes, the limit is {MAX_LOGIN_BYTES}")),
+        _ => return,
+    };
+    let login = match login {
+        Ok(login) => login,
+        Err(e) => {
+            let _ = write_line(&mut writer, nack(None, "MalformedMessage", e).into()).await;
+            return;
+        }
+    };
+    let Some(seats) = tokens.get(&login.token) else {
+        log::warn!("bot login rejected, room id: {}, player id: {}", login.room_id, login.player_id);
+        let _ = write_line(&mut writer, nack(None, "Unauthorized", "invalid bot token").into()).await;
+        return;
+    };
+    // 每个 token 只能使用配置里为它指定的座位，不能占用真人的座位
+    if !seats.contains(&login.player_id) {
+        log::warn!(
+            "bot token not allowed for seat, room id: {}, player id: {}", login.room_id, login.player_id
+        );
+        let reason = format!("token is not allowed to take seat {}", login.player_id);
+        let _ = write_line(&mut writer, nack(None, "Forbidden", reason).into()).await;
+        return;
+    }
+    let BotLogin { room_id, player_id, .. } = login;
+
+    let Some(mut participant) = take_participant(&human_participants, &room_id, &player_id).await else {
+        let _ = write_line(&mut writer, nack(None, "SeatUnavailable", "seat not found or already connected").into()).await;
+        return;
+    };
+
+    let _connection = ConnectionGuard::new("tcp");
+    let action_tx = participant.action_sender.clone();
+    let _ = action_tx.send(Command::Connected(player_id.clone())).await;
+
+    let (control_tx, mut control_rx) = channel::<ControlMessage>(16);
+    let _ = control_tx.send(hello(&player_id, &participant.room)).await;
+    let event_rx = &mut participant.event_receiver;
+
+    // event / control → socket
+    let send_loop = async {
+        loop {
+            let message: ServerMessage = select! {
+                Some(control) = control_rx.recv() => control.into(),
+                event = event_rx.recv() => match event {
+                    Some(event) => event.into(),
+                    None => break,
+                },
+                else => break,
+            };
+            if write_line(&mut writer, message).await.is_err() {
+                break;
+            }
+        }
+    };
+
+    // socket → action；返回 true 表示因反复超限被踢出
+    let mut flood = FloodGuard::new(participant.limits, Instant::now());
+    let max_message_bytes = participant.limits.max_message_bytes;
+    let recv_loop = async {
+        while let Ok(Some(line)) = read_line(&mut reader, max_message_bytes).await {
+            let (line, size) = match line {
+                Line::Text(line) if line.trim().is_empty() => continue,
+                Line::Text(line) => {
+                    let size = line.len();
+                    (line, size)
+                }
+                Line::TooLong(size) => (String::new(), size),
+            };
+            let reply = match flood.check(size, Instant::now()) {
+                Ok(()) => handle_client_text(&line, &player_id, participant.controls, &action_tx).await,
+                Err(Violation::Kick) => return true,
+                Err(Violation::RateLimited) => Some(Violation::RateLimited.nack(text_request_id(&line))),
+                Err(violation) => Some(violation.nack(None)),
+            };
+            if let Some(reply) = reply
+                && control_tx.send(reply).await.is_err() {
+                break;
+            }
+        }
+        false
+    };
+
+    let kicked = select! {
+        _ = send_loop => false,
+        kicked = recv_loop => kicked,
+    };
+
+    if kicked {
+        let _ = write_line(&mut writer, Violation::Kick.nack(None).into()).await;
+        limits::kick(&action_tx, participant.room.clone(), &player_id).await;
+    }
+
+    let _ = action_tx.send(Command::Disconnected(player_id.clone())).await;
+    if !participant.event_receiver.is_closed() {
+        human_participants.lock().await.push(participant);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatcher::create_participant;
+    use figgie_core::*;
+    use tokio::io::AsyncBufReadExt;
+    use tokio::sync::Mutex;
+    use crate::limits::FloodLimits;
+    use crate::test_support::config;
+
+    fn room() -> Arc<GameConfig> {
+        Arc::new(config("R1"))
+    }
+
+    fn tokens() -> BotTokens {
+        Arc::new(HashMap::from([("secret".to_string(), vec!["bot1".to_string()])]))
+    }
+
+    #[tokio::test]
+    async fn bot_logs_in_and_receives_hello() {
+        let (command_tx, mut command_rx) = channel::<Command>(8);
+        let (participant, _event_tx) = create_participant("bot1".to_string(), room(), command_tx.clone(), 8);
+        let (human, _human_tx) = create_participant("alice".to_string(), room(), command_tx, 8);
+        let pool: HumanParticipants = Arc::new(Mutex::new(vec![participant, human]));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_bots(listener, tokens(), pool.clone()));
+
+        // 错误的 token 被拒绝
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"{\"token\":\"nope\",\"room_id\":\"R1\",\"player_id\":\"bot1\"}\n").await.unwrap();
+        let reply = lines.next_line().await.unwrap().unwrap();
+        assert!(reply.contains("Unauthorized"));
+
+        // token 只能用于指定的座位，真人的座位不行
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"{\"token\":\"secret\",\"room_id\":\"R1\",\"player_id\":\"alice\"}\n").await.unwrap();
+        let reply = lines.next_line().await.unwrap().unwrap();
+        assert!(reply.contains("Forbidden"));
+        assert_eq!(pool.lock().await.len(), 2);
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"{\"token\":\"secret\",\"room_id\":\"R1\",\"player_id\":\"bot1\"}\n").await.unwrap();
+        let reply = lines.next_line().await.unwrap().unwrap();
+        assert!(reply.contains("\"Hello\""));
+        assert!(matches!(command_rx.recv().await, Some(Command::Connected(id)) if id == "bot1"));
+
+        writer.write_all(b"{\"type\":\"StartRound\",\"payload\":{\"round_id\":2,\"room_id\":\"R1\"}}\n").await.unwrap();
+        assert!(matches!(command_rx.recv().await, Some(Command::Action(ActionRequest { action: Action::StartRound(2), .. }))));
+
+        // 断开后座位回到等待队列
+        drop(writer);
+        drop(lines);
+        assert!(matches!(command_rx.recv().await, Some(Command::Disconnected(id)) if id == "bot1"));
+    }
+
+    #[tokio::test]
+    async fn flooding_bot_is_rate_limited_then_kicked() {
+        let (command_tx, mut command_rx) = channel::<Command>(8);
+        let (mut participant, _event_tx) = create_participant("bot1".to_string(), room(), command_tx, 8);
+        participant.limits = FloodLimits {
+            messages_per_sec: 0.0,
+            burst: 1,
+            max_message_bytes: 200,
+            kick_after_violations: 3,
+            violation_window_secs: 60,
+        };
+        let pool: HumanParticipants = Arc::new(Mutex::new(vec![participant]));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_bots(listener, tokens(), pool.clone()));
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"{\"token\":\"secret\",\"room_id\":\"R1\",\"player_id\":\"bot1\"}\n").await.unwrap();
+        assert!(lines.next_line().await.unwrap().unwrap().contains("\"Hello\""));
+        assert!(matches!(command_rx.recv().await, Some(Command::Connected(_))));
+
+        let start_round = b"{\"id\":\"r1\",\"type\":\"StartRound\",\"payload\":{\"round_id\":2,\"room_id\":\"R1\"}}\n";
+        writer.write_all(start_round).await.unwrap();
+        let Some(Command::Action(ActionRequest { reply: Some(reply), .. })) = command_rx.recv().await else {
+            panic!("expected an action");
+        };
+        reply.send(Ok(())).unwrap();
+        assert!(lines.next_line().await.unwrap().unwrap().contains("Ack"));
+
+        // 令牌用完后直接回 Nack，不进入 dispatcher
+        writer.write_all(start_round).await.unwrap();
+        let reply = lines.next_line().await.unwrap().unwrap();
+        assert!(reply.contains("RateLimited") && reply.contains("\"r1\""));
+        // 超长的行不会整行读入内存，只回 Nack
+        writer.write_all(format!("{}\n", "x".repeat(100_000)).as_bytes()).await.unwrap();
+        let reply = lines.next_line().await.unwrap().unwrap();
+        assert!(reply.contains("MessageTooLarge") && reply.contains("100000 bytes"));
+
+        writer.write_all(start_round).await.unwrap();
+        assert!(lines.next_line().await.unwrap().unwrap().contains("Kicked"));
+        assert!(matches!(command_rx.recv().await, Some(Command::Reseat { robot: false, .. })));
+        assert!(matches!(command_rx.recv().await, Some(Command::Disconnected(_))));
+    }
+}
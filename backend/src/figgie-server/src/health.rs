@@ -0,0 +1,57 @@
+use crate::app::AppState;
+use crate::protocol::{PROTOCOL_VERSION, SERVER_VERSION};
+
+use std::sync::atomic::Ordering;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde_json::json;
+
+// 构建时由 build.rs 写入
+pub const GIT_HASH: &str = env!("FIGGIE_GIT_HASH");
+
+// 进程还在响应请求即可
+pub async fn healthz() -> impl IntoResponse {
+    Json(json!({ "status": "ok" }))
+}
+
+// 可以接新房间：没有在停机，房间数也没到上限
+pub async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+    if state.shutting_down.load(Ordering::SeqCst) {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "status": "shutting down" })));
+    }
+    let rooms = state.dispatchers.lock().await.len();
+    let max_rooms = state.config.rooms.max_rooms;
+    if rooms >= max_rooms {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "status": "room limit reached", "rooms": rooms, "max_rooms": max_rooms })),
+        );
+    }
+    (StatusCode::OK, Json(json!({ "status": "ready", "rooms": rooms, "max_rooms": max_rooms })))
+}
+
+pub async fn version() -> impl IntoResponse {
+    Json(json!({
+        "version": SERVER_VERSION,
+        "git_hash": GIT_HASH,
+        "protocol_version": PROTOCOL_VERSION,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Sessions;
+    use crate::config::ServerConfig;
+    use crate::store::Store;
+
+    #[tokio::test]
+    async fn ready_until_shutdown() {
+        let state = AppState::new(ServerConfig::default(), Store::in_memory().unwrap(), Sessions::new(vec![7; 32], 60));
+        assert_eq!(healthz().await.into_response().status(), StatusCode::OK);
+        assert_eq!(readyz(State(state.clone())).await.into_response().status(), StatusCode::OK);
+        assert!(!GIT_HASH.is_empty());
+
+        state.shutting_down.store(true, Ordering::SeqCst);
+        assert_eq!(readyz(State(state)).await.into_response().status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::dispatcher::{create_participant, Command};
+use crate::metrics::METRICS;
+use crate::protocol::{nack, ControlMessage};
+
+use std::sync::Arc;
+use figgie_core::GameConfig;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc::Sender, oneshot};
+use tokio::time::{Duration, Instant};
+
+// 每条连接的入站限制，在消息进入 dispatcher 之前检查；可按房间覆盖
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FloodLimits {
+    // 令牌桶：每秒补充的消息数和桶的容量
+    pub messages_per_sec: f64,
+    pub burst: u32,
+    // 单条消息的最大字节数
+    pub max_message_bytes: usize,
+    // violation_window_secs 内被拒绝这么多次就踢出座位；0 表示不踢
+    pub kick_after_violations: u32,
+    pub violation_window_secs: u64,
+}
+
+impl Default for FloodLimits {
+    fn default() -> Self {
+        FloodLimits {
+            messages_per_sec: 10.0,
+            burst: 20,
+            max_message_bytes: 4096,
+            kick_after_violations: 50,
+            violation_window_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Violation {
+    TooLarge { size: usize, max: usize },
+    RateLimited,
+    // 多次违规，应当断开并踢出
+    Kick,
+}
+
+impl Violation {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Violation::TooLarge { .. } => "MessageTooLarge",
+            Violation::RateLimited => "RateLimited",
+            Violation::Kick => "Kicked",
+        }
+    }
+
+    pub fn nack(&self, id: Option<String>) -> ControlMessage {
+        let reason = match self {
+            Violation::TooLarge { size, max } => format!("message is {size} bytes, the limit is {max}"),
+            Violation::RateLimited => "too many messages, slow down".to_string(),
+            Violation::Kick => "too many rejected messages".to_string(),
+        };
+        nack(id, self.code(), reason)
+    }
+}
+
+#[derive(Debug)]
+pub struct FloodGuard {
+    limits: FloodLimits,
+    tokens: f64,
+    refilled_at: Instant,
+    violations: u32,
+    // 当前统计窗口的起点，即窗口内第一次违规的时间
+    window_start: Instant,
+}
+
+impl FloodGuard {
+    pub fn new(limits: FloodLimits, now: Instant) -> Self {
+        FloodGuard {
+            limits,
+            tokens: limits.burst as f64,
+            refilled_at: now,
+            violations: 0,
+            window_start: now,
+        }
+    }
+
+    // 超长的消息不消耗令牌；每次拒绝都计入违规次数
+    pub fn check(&mut self, size: usize, now: Instant) -> Result<(), Violation> {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limits.messages_per_sec).min(self.limits.burst as f64);
+        self.refilled_at = now;
+
+        let violation = if size > self.limits.max_message_bytes {
+            Violation::TooLarge { size, max: self.limits.max_message_bytes }
+        } else if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        } else {
+            Violation::RateLimited
+        };
+        METRICS.rejected_actions.with_label_values(&[violation.code()]).inc();
+
+        let window = Duration::from_secs(self.limits.violation_window_secs);
+        if self.violations == 0 || now.saturating_duration_since(self.window_start) >= window {
+            self.violations = 0;
+            self.window_start = now;
+        }
+        self.violations += 1;
+        if self.limits.kick_after_violations > 0 && self.violations >= self.limits.kick_after_violations {
+            return Err(Violation::Kick);
+        }
+        Err(violation)
+    }
+}
+
+// 踢出座位：换上一个立即丢弃的出站队列，与管理员踢人相同
+pub async fn kick(action_sender: &Sender<Command>, room: Arc<GameConfig>, player_id: &str) {
+    log::warn!("player kicked for flooding, room id: {}, player id: {}", room.room_id, player_id);
+    let (_, outbox) = create_participant(player_id.to_string(), room, action_sender.clone(), 1);
+    let (reply, _) = oneshot::channel();
+    let _ = action_sender
+        .send(Command::Reseat { player_id: player_id.to_string(), outbox, robot: false, reply })
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> FloodLimits {
+        FloodLimits {
+            messages_per_sec: 2.0,
+            burst: 3,
+            max_message_bytes: 100,
+            kick_after_violations: 4,
+            violation_window_secs: 10,
+        }
+    }
+
+    #[test]
+    fn token_bucket_refills_over_time() {
+        let start = Instant::now();
+        let mut guard = FloodGuard::new(limits(), start);
+        for _ in 0..3 {
+            assert_eq!(guard.check(10, start), Ok(()));
+        }
+        assert_eq!(guard.check(10, start), Err(Violation::RateLimited));
+        assert_eq!(guard.check(101, start), Err(Violation::TooLarge { size: 101, max: 100 }));
+
+        // 半秒补一个令牌，最多补满 burst
+        assert_eq!(guard.check(10, start + Duration::from_millis(500)), Ok(()));
+        let later = start + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert_eq!(guard.check(10, later), Ok(()));
+        }
+        assert_eq!(guard.check(10, later), Err(Violation::RateLimited));
+    }
+
+    #[test]
+    fn repeat_offenders_are_kicked() {
+        let start = Instant::now();
+        let mut guard = FloodGuard::new(FloodLimits { burst: 0, ..limits() }, start);
+        for _ in 0..3 {
+            assert_eq!(guard.check(10, start), Err(Violation::RateLimited));
+        }
+        // 窗口过去后重新计数
+        let later = start + Duration::from_secs(10);
+        for _ in 0..3 {
+            assert_eq!(guard.check(1_000, later), Err(Violation::TooLarge { size: 1_000, max: 100 }));
+        }
+        assert_eq!(guard.check(10, later), Err(Violation::Kick));
+    }
+}
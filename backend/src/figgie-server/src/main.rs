@@ -3,22 +3,148 @@ mod types;
 mod dispatcher;
 mod robots;
 mod adapter;
+mod protocol;
+mod outbox;
+mod gateway;
+mod config;
+mod snapshot;
+mod store;
+mod auth;
+mod rating;
+mod chat;
+mod matchmaking;
+mod admin;
+mod metrics;
+mod limits;
+mod health;
+mod frontend;
+mod cors;
+mod tls;
+#[cfg(test)]
+mod test_support;
 
-use std::net::SocketAddr;
+use std::sync::Arc;
+use clap::Parser;
 use tokio::net::TcpListener;
 
 #[tokio::main]
 async fn main() {
+    let cli = config::Cli::parse();
+    let config = match config::ServerConfig::load(&cli) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("配置加载失败：{e}");
+            std::process::exit(2);
+        }
+    };
+
+    // RUST_LOG 优先，其次是命令行 / 配置文件中的日志级别
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(&config.server.log_level)).init();
+
+    if let Err(e) = std::fs::create_dir_all(&config.server.data_dir) {
+        log::error!("无法创建数据目录 {}：{}", config.server.data_dir.display(), e);
+        std::process::exit(2);
+    }
+
+    let db_path = config.server.data_dir.join(store::DB_FILE);
+    let store = match store::Store::open(&db_path) {
+        Ok(store) => store,
+        Err(e) => {
+            log::error!("无法打开数据库 {}：{}", db_path.display(), e);
+            std::process::exit(2);
+        }
+    };
+
+    let sessions = match auth::Sessions::load(&config.auth.secret, &config.server.data_dir, config.auth.token_ttl_secs) {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            log::error!("无法加载会话密钥：{}", e);
+            std::process::exit(2);
+        }
+    };
+
     // 构建应用路由
-    let app = app::create_app();
+    let addr = config.server.bind;
+    let state = app::AppState::new(config, store, sessions);
+    let app = app::create_app(state.clone());
+    app::restore_rooms(&state).await;
+    tokio::spawn(matchmaking::run(state.clone()));
+
+    // 外部机器人的 TCP 接入，未配置 token 时不启用
+    let bot_tokens = state.config.server.bot_tokens.clone();
+    if bot_tokens.is_empty() {
+        log::info!("未配置 bot_tokens，机器人 TCP 接入未启用");
+    } else {
+        let bot_addr = state.config.server.bot_bind;
+        let bot_listener = TcpListener::bind(bot_addr).await.expect("机器人接入监听失败");
+        log::info!("机器人 TCP 接入已启动：{}", bot_addr);
+        tokio::spawn(gateway::serve_bots(bot_listener, Arc::new(bot_tokens), state.human_participants.clone()));
+    }
+
+    // 证书和私钥都配置时启用 HTTPS / WSS
+    let tls = match (&state.config.server.tls_cert, &state.config.server.tls_key) {
+        (Some(cert), Some(key)) => match tls::load(cert, key).await {
+            Ok(tls) => Some(tls),
+            Err(e) => {
+                log::error!("无法加载 TLS 证书 {}：{}", cert.display(), e);
+                std::process::exit(2);
+            }
+        },
+        _ => None,
+    };
 
     // 监听地址
-    let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
     let listener = TcpListener::bind(addr).await.expect("监听失败");
-    println!("服务器已启动：http://{}", listener.local_addr().unwrap());
 
-    // 启动服务（axum 0.8 使用 axum::serve）
-    axum::serve(listener, app.into_make_service())
-        .await
-        .expect("服务器启动失败");
+    // 收到停机信号后先保存房间再退出
+    let shutdown = async move {
+        shutdown_signal().await;
+        app::shutdown(&state).await;
+    };
+    match tls {
+        Some(tls) => {
+            log::info!("服务器已启动：https://{}", addr);
+            tls::serve(listener, app, tls, shutdown).await.expect("服务器启动失败");
+        }
+        None => {
+            log::info!("服务器已启动：http://{}", addr);
+            // axum 0.8 使用 axum::serve
+            axum::serve(listener, app.into_make_service())
+                .with_graceful_shutdown(shutdown)
+                .await
+                .expect("服务器启动失败");
+        }
+    }
+    log::info!("服务器已停止");
+}
+
+// ctrl-c 或 SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            log::error!("无法监听 ctrl-c：{}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                log::error!("无法监听 SIGTERM：{}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    log::info!("收到停机信号");
 }
@@ -0,0 +1,348 @@
+use crate::app::{launch_room, AppState};
+use crate::auth::AuthUser;
+use crate::types::{PlayerInfo, StartGameRequest};
+
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::time::{interval, Duration, Instant};
+
+// 随机生成的房间号撞上现有房间时，换一个重试的次数
+const LAUNCH_ATTEMPTS: usize = 5;
+
+// 排队偏好：只有 seats 和 rated 都相同的玩家才会坐到一桌
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    // 4 或 5 人桌
+    pub seats: usize,
+    pub rated: bool,
+    // 等待超时后是否接受机器人补位
+    pub allow_bots: bool,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Preferences { seats: 4, rated: true, allow_bots: true }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Ticket {
+    pub player: PlayerInfo,
+    pub prefs: Preferences,
+    pub enqueued_at: Instant,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Match {
+    pub room_id: String,
+    pub game_id: i64,
+}
+
+// 凑齐的一桌，空位由机器人补齐
+#[derive(Debug)]
+pub struct Table {
+    pub seats: usize,
+    pub rated: bool,
+    pub players: Vec<Ticket>,
+}
+
+impl Table {
+    pub fn robots(&self) -> usize {
+        self.seats.saturating_sub(self.players.len())
+    }
+
+    fn request(&self) -> StartGameRequest {
+        let tag = format!("{:08x}", rand::random::<u32>());
+        let mut players: Vec<PlayerInfo> = self.players.iter().map(|t| t.player.clone()).collect();
+        players.extend((1..=self.robots()).map(|i| PlayerInfo {
+            id: format!("robot{i}"),
+            name: format!("Robot {i}"),
+        }));
+        StartGameRequest {
+            room_name: format!("Match {tag}"),
+            room_id: format!("mm-{tag}"),
+            players,
+            rated: Some(self.rated),
+            ..StartGameRequest::default()
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum QueueStatus {
+    Idle,
+    Waiting {
+        prefs: Preferences,
+        waited_secs: u64,
+        // 同样偏好的排队人数，包括自己
+        compatible: usize,
+    },
+    Matched(Match),
+}
+
+#[derive(Debug, Default)]
+pub struct Matchmaker {
+    // 按入队先后排列
+    waiting: Vec<Ticket>,
+    // 已经开桌的玩家，重新排队、离开队列或房间关闭时清除
+    matched: HashMap<String, Match>,
+}
+
+impl Matchmaker {
+    // 重复排队时以新的偏好为准，等待时间重新计算
+    pub fn enqueue(&mut self, player: PlayerInfo, prefs: Preferences, now: Instant) {
+        self.leave(&player.id);
+        self.waiting.push(Ticket { player, prefs, enqueued_at: now });
+    }
+
+    pub fn leave(&mut self, player_id: &str) -> bool {
+        let matched = self.matched.remove(player_id).is_some();
+        let before = self.waiting.len();
+        self.waiting.retain(|t| t.player.id != player_id);
+        matched || self.waiting.len() != before
+    }
+
+    pub fn status(&self, player_id: &str, now: Instant) -> QueueStatus {
+        if let Some(found) = self.matched.get(player_id) {
+            return QueueStatus::Matched(found.clone());
+        }
+        let Some(ticket) = self.waiting.iter().find(|t| t.player.id == player_id) else {
+            return QueueStatus::Idle;
+        };
+        let prefs = ticket.prefs;
+        QueueStatus::Waiting {
+            prefs,
+            waited_secs: now.saturating_duration_since(ticket.enqueued_at).as_secs(),
+            compatible: self
+                .waiting
+                .iter()
+                .filter(|t| t.prefs.seats == prefs.seats && t.prefs.rated == prefs.rated)
+                .count(),
+        }
+    }
+
+    // 人满即开桌；同组等待最久的玩家超过 robot_fill 后，用机器人补齐愿意和机器人打的玩家
+    pub fn form_tables(&mut self, now: Instant, robot_fill: Duration) -> Vec<Table> {
+        let mut groups: Vec<(usize, bool)> = vec![];
+        for ticket in &self.waiting {
+            let key = (ticket.prefs.seats, ticket.prefs.rated);
+            if !groups.contains(&key) {
+                groups.push(key);
+            }
+        }
+
+        let mut tables = vec![];
+        for (seats, rated) in groups {
+            loop {
+                let group: Vec<usize> = (0..self.waiting.len())
+                    .filter(|&i| self.waiting[i].prefs.seats == seats && self.waiting[i].prefs.rated == rated)
+                    .collect();
+                let picked: Vec<usize> = if group.len() >= seats {
+                    group.into_iter().take(seats).collect()
+                } else {
+                    let with_bots: Vec<usize> = group.into_iter().filter(|&i| self.waiting[i].prefs.allow_bots).collect();
+                    match with_bots.first() {
+                        Some(&oldest) if now.saturating_duration_since(self.waiting[oldest].enqueued_at) >= robot_fill => with_bots,
+                        _ => break,
+                    }
+                };
+                tables.push(Table { seats, rated, players: self.take(&picked) });
+            }
+        }
+        tables
+    }
+
+    fn take(&mut self, indices: &[usize]) -> Vec<Ticket> {
+        let mut taken: Vec<Ticket> = indices.iter().rev().map(|&i| self.waiting.remove(i)).collect();
+        taken.reverse();
+        taken
+    }
+
+    pub fn matched(&mut self, table: &Table, found: Match) {
+        for ticket in &table.players {
+            self.matched.insert(ticket.player.id.clone(), found.clone());
+        }
+    }
+
+    // 房间结束后不再把玩家指向它
+    pub fn room_closed(&mut self, room_id: &str) {
+        self.matched.retain(|_, found| found.room_id != room_id);
+    }
+
+    // 开桌失败时放回队列，保留原来的入队时间
+    pub fn requeue(&mut self, table: Table) {
+        self.waiting.extend(table.players);
+        self.waiting.sort_by_key(|t| t.enqueued_at);
+    }
+}
+
+// 后台定时撮合
+pub async fn run(state: AppState) {
+    let settings = state.config.matchmaking.clone();
+    let mut tick = interval(Duration::from_millis(settings.tick_millis.max(100)));
+    loop {
+        tick.tick().await;
+        if state.shutting_down.load(Ordering::SeqCst) {
+            break;
+        }
+        form_and_launch(&state).await;
+    }
+}
+
+async fn form_and_launch(state: &AppState) {
+    let robot_fill = Duration::from_secs(state.config.matchmaking.robot_fill_secs);
+    let tables = state.matchmaker.lock().await.form_tables(Instant::now(), robot_fill);
+    for table in tables {
+        let (room_id, result) = launch_table(state, &table).await;
+        match result {
+            Ok(game_id) => {
+                log::info!(
+                    "match formed, room id: {}, humans: {}, robots: {}, rated: {}",
+                    room_id, table.players.len(), table.robots(), table.rated
+                );
+                state.matchmaker.lock().await.matched(&table, Match { room_id, game_id });
+            }
+            Err((_, e)) => {
+                log::warn!("failed to open match room {}: {}", room_id, e);
+                state.matchmaker.lock().await.requeue(table);
+            }
+        }
+    }
+}
+
+async fn launch_table(state: &AppState, table: &Table) -> (String, Result<i64, (StatusCode, String)>) {
+    let mut attempt = 1;
+    loop {
+        let req = table.request();
+        let room_id = req.room_id.clone();
+        match launch_room(state, req, None).await {
+            Err((StatusCode::CONFLICT, e)) if attempt < LAUNCH_ATTEMPTS => {
+                log::info!("match room id {} taken, retrying: {}", room_id, e);
+                attempt += 1;
+            }
+            result => return (room_id, result),
+        }
+    }
+}
+
+pub async fn enqueue(
+    State(state): State<AppState>,
+    AuthUser(player_id): AuthUser,
+    Json(prefs): Json<Preferences>,
+) -> impl IntoResponse {
+    if !(4..=5).contains(&prefs.seats) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "seats must be 4 or 5" })));
+    }
+    let store = state.store.clone();
+    let id = player_id.clone();
+    let name = match tokio::task::spawn_blocking(move || store.user(&id)).await {
+        Ok(Ok(Some(user))) => user.profile.display_name,
+        Ok(Ok(None)) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "player not found" }))),
+        Ok(Err(e)) => return queue_error(e),
+        Err(e) => return queue_error(e),
+    };
+
+    state
+        .matchmaker
+        .lock()
+        .await
+        .enqueue(PlayerInfo { id: player_id.clone(), name }, prefs, Instant::now());
+    log::info!("player queued, player id: {}, prefs: {:?}", player_id, prefs);
+    form_and_launch(&state).await;
+
+    let status = state.matchmaker.lock().await.status(&player_id, Instant::now());
+    (StatusCode::OK, Json(json!(status)))
+}
+
+pub async fn status(State(state): State<AppState>, AuthUser(player_id): AuthUser) -> impl IntoResponse {
+    Json(state.matchmaker.lock().await.status(&player_id, Instant::now()))
+}
+
+pub async fn leave(State(state): State<AppState>, AuthUser(player_id): AuthUser) -> impl IntoResponse {
+    if state.matchmaker.lock().await.leave(&player_id) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+fn queue_error(e: impl std::fmt::Display) -> (StatusCode, Json<serde_json::Value>) {
+    log::error!("storage error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": "storage error" })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(id: &str) -> PlayerInfo {
+        PlayerInfo { id: id.to_string(), name: id.to_uppercase() }
+    }
+
+    fn prefs(seats: usize, rated: bool, allow_bots: bool) -> Preferences {
+        Preferences { seats, rated, allow_bots }
+    }
+
+    #[test]
+    fn forms_full_tables_from_compatible_players() {
+        let mut mm = Matchmaker::default();
+        let now = Instant::now();
+        let fill = Duration::from_secs(30);
+        for id in ["a", "b", "c"] {
+            mm.enqueue(player(id), prefs(4, true, false), now);
+        }
+        mm.enqueue(player("x"), prefs(4, false, false), now);
+        mm.enqueue(player("y"), prefs(5, true, false), now);
+        assert!(mm.form_tables(now, fill).is_empty());
+        assert!(matches!(mm.status("a", now), QueueStatus::Waiting { compatible: 3, .. }));
+
+        mm.enqueue(player("d"), prefs(4, true, true), now);
+        let tables = mm.form_tables(now, fill);
+        assert_eq!(tables.len(), 1);
+        let ids: Vec<&str> = tables[0].players.iter().map(|t| t.player.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c", "d"]);
+        assert_eq!(tables[0].robots(), 0);
+        assert!(matches!(mm.status("a", now), QueueStatus::Idle));
+        assert!(matches!(mm.status("x", now), QueueStatus::Waiting { compatible: 1, .. }));
+
+        mm.matched(&tables[0], Match { room_id: "mm-1".to_string(), game_id: 7 });
+        assert!(matches!(mm.status("b", now), QueueStatus::Matched(Match { game_id: 7, .. })));
+
+        mm.room_closed("mm-2");
+        assert!(matches!(mm.status("b", now), QueueStatus::Matched(..)));
+        mm.room_closed("mm-1");
+        assert!(matches!(mm.status("b", now), QueueStatus::Idle));
+    }
+
+    #[test]
+    fn fills_with_robots_after_timeout() {
+        let mut mm = Matchmaker::default();
+        let start = Instant::now();
+        let fill = Duration::from_secs(30);
+        mm.enqueue(player("a"), prefs(5, true, true), start);
+        mm.enqueue(player("b"), prefs(5, true, false), start + Duration::from_secs(5));
+        mm.enqueue(player("c"), prefs(5, true, true), start + Duration::from_secs(10));
+        assert!(mm.form_tables(start + Duration::from_secs(29), fill).is_empty());
+
+        // 不接受机器人的玩家继续排队
+        let tables = mm.form_tables(start + fill, fill);
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].players.len(), 2);
+        assert_eq!(tables[0].robots(), 3);
+        let req = tables[0].request();
+        assert_eq!(req.players.len(), 5);
+        assert_eq!(req.players[4].id, "robot3");
+        assert!(matches!(mm.status("b", start + fill), QueueStatus::Waiting { .. }));
+
+        // 开桌失败放回队列
+        mm.requeue(tables.into_iter().next().unwrap());
+        assert!(matches!(mm.status("a", start + fill), QueueStatus::Waiting { waited_secs: 30, compatible: 3, .. }));
+        assert!(mm.leave("a"));
+        assert!(!mm.leave("a"));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::app::AppState;
+
+use std::sync::LazyLock;
+use axum::{extract::State, http::header, response::IntoResponse};
+use figgie_core::Action;
+use crate::dispatcher::ActionError;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+
+// 延迟分桶（秒），撮合和分发通常在微秒级
+const LATENCY_BUCKETS: &[f64] = &[0.000_01, 0.000_05, 0.000_1, 0.000_5, 0.001, 0.005, 0.01, 0.05, 0.1];
+
+pub struct Metrics {
+    registry: Registry,
+    pub rooms: IntGauge,
+    // 按接入方式区分：websocket / tcp
+    pub connected_players: IntGaugeVec,
+    // 进入引擎的 action，按类型计数；每秒速率由 Prometheus 的 rate() 计算
+    pub actions: IntCounterVec,
+    pub rejected_actions: IntCounterVec,
+    pub match_latency: HistogramVec,
+    pub fanout_latency: Histogram,
+    pub robot_actions: IntCounterVec,
+    // 出站队列，按房间统计
+    pub outbox_depth: IntGaugeVec,
+    pub outbox_coalesced: IntCounterVec,
+    pub outbox_dropped: IntCounterVec,
+    pub outbox_overflows: IntCounterVec,
+}
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("figgie".to_string()), None).expect("valid metrics prefix");
+        let metrics = Metrics {
+            rooms: IntGauge::new("rooms", "Rooms currently running").unwrap(),
+            connected_players: IntGaugeVec::new(
+                Opts::new("connected_players", "Players currently connected"),
+                &["transport"],
+            )
+            .unwrap(),
+            actions: IntCounterVec::new(Opts::new("actions_total", "Actions handled by the engine"), &["action"]).unwrap(),
+            rejected_actions: IntCounterVec::new(
+                Opts::new("rejected_actions_total", "Actions rejected, by error code"),
+                &["reason"],
+            )
+            .unwrap(),
+            match_latency: HistogramVec::new(
+                HistogramOpts::new("match_latency_seconds", "Time spent in Game::handle_action")
+                    .buckets(LATENCY_BUCKETS.to_vec()),
+                &["action"],
+            )
+            .unwrap(),
+            fanout_latency: Histogram::with_opts(
+                HistogramOpts::new("fanout_latency_seconds", "Time spent handing events to participant queues")
+                    .buckets(LATENCY_BUCKETS.to_vec()),
+            )
+            .unwrap(),
+            robot_actions: IntCounterVec::new(
+                Opts::new("robot_actions_total", "Actions sent by built-in robots"),
+                &["action"],
+            )
+            .unwrap(),
+            outbox_depth: IntGaugeVec::new(
+                Opts::new("outbox_depth", "Events waiting in participant outboxes"),
+                &["room"],
+            )
+            .unwrap(),
+            outbox_coalesced: IntCounterVec::new(
+                Opts::new("outbox_coalesced_total", "Book updates merged in backed-up outboxes"),
+                &["room"],
+            )
+            .unwrap(),
+            outbox_dropped: IntCounterVec::new(
+                Opts::new("outbox_dropped_total", "Events dropped from full outboxes of absent players"),
+                &["room"],
+            )
+            .unwrap(),
+            outbox_overflows: IntCounterVec::new(
+                Opts::new("outbox_overflows_total", "Slow consumers disconnected for a full outbox"),
+                &["room"],
+            )
+            .unwrap(),
+            registry,
+        };
+        let r = &metrics.registry;
+        r.register(Box::new(metrics.rooms.clone())).unwrap();
+        r.register(Box::new(metrics.connected_players.clone())).unwrap();
+        r.register(Box::new(metrics.actions.clone())).unwrap();
+        r.register(Box::new(metrics.rejected_actions.clone())).unwrap();
+        r.register(Box::new(metrics.match_latency.clone())).unwrap();
+        r.register(Box::new(metrics.fanout_latency.clone())).unwrap();
+        r.register(Box::new(metrics.robot_actions.clone())).unwrap();
+        r.register(Box::new(metrics.outbox_depth.clone())).unwrap();
+        r.register(Box::new(metrics.outbox_coalesced.clone())).unwrap();
+        r.register(Box::new(metrics.outbox_dropped.clone())).unwrap();
+        r.register(Box::new(metrics.outbox_overflows.clone())).unwrap();
+        metrics
+    }
+
+    pub fn rejected(&self, e: &ActionError) {
+        self.rejected_actions.with_label_values(&[e.code()]).inc();
+    }
+
+    // 房间关闭后去掉它的队列指标，避免留下过期的序列
+    pub fn forget_room(&self, room_id: &str) {
+        let _ = self.outbox_depth.remove_label_values(&[room_id]);
+        let _ = self.outbox_coalesced.remove_label_values(&[room_id]);
+        let _ = self.outbox_dropped.remove_label_values(&[room_id]);
+        let _ = self.outbox_overflows.remove_label_values(&[room_id]);
+    }
+
+    pub fn render(&self) -> String {
+        let mut buf = vec![];
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buf) {
+            log::error!("failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buf).unwrap_or_default()
+    }
+}
+
+pub fn action_label(action: &Action) -> &'static str {
+    match action {
+        Action::StartRound(_) => "StartRound",
+        Action::PlaceQuote(_) => "PlaceQuote",
+        Action::CancelQuote(_) => "CancelQuote",
+        Action::EndRound => "EndRound",
+        Action::EndGame => "EndGame",
+        Action::Pause => "Pause",
+        Action::Resume => "Resume",
+        Action::Chat { .. } => "Chat",
+    }
+}
+
+// 连接存活期间计入在线人数，drop 时减掉
+pub struct ConnectionGuard(&'static str);
+
+impl ConnectionGuard {
+    pub fn new(transport: &'static str) -> Self {
+        METRICS.connected_players.with_label_values(&[transport]).inc();
+        ConnectionGuard(transport)
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        METRICS.connected_players.with_label_values(&[self.0]).dec();
+    }
+}
+
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    METRICS.rooms.set(state.dispatchers.lock().await.len() as i64);
+    ([(header::CONTENT_TYPE, TextEncoder::new().format_type().to_string())], METRICS.render())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use figgie_core::FiggieError;
+
+    #[test]
+    fn renders_text_exposition() {
+        METRICS.actions.with_label_values(&["PlaceQuote"]).inc();
+        METRICS.rejected(&FiggieError::Paused.into());
+        let guard = ConnectionGuard::new("test");
+        let text = METRICS.render();
+        assert!(text.contains("figgie_actions_total{action=\"PlaceQuote\"}"));
+        assert!(text.contains("figgie_rejected_actions_total{reason=\"Paused\"}"));
+        assert!(text.contains("figgie_connected_players{transport=\"test\"} 1"));
+        drop(guard);
+        assert!(METRICS.render().contains("figgie_connected_players{transport=\"test\"} 0"));
+        assert!(text.contains("# TYPE figgie_fanout_latency_seconds histogram"));
+    }
+}
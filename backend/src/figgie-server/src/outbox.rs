@@ -0,0 +1,247 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use figgie_core::{Event, Side, Suit};
+use serde::Serialize;
+use tokio::sync::Notify;
+
+// 每个参与者的待发送事件上限，超过即视为消费过慢
+pub const OUTBOX_CAPACITY: usize = 256;
+
+// 积压超过这个比例后开始合并盘口更新
+const COALESCE_RATIO: usize = 2;
+
+// 出站队列的统计信息
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct OutboxStats {
+    pub capacity: usize,
+    // 当前积压的事件数
+    pub depth: usize,
+    // 历史最大积压
+    pub high_water: usize,
+    // 被合并掉的盘口更新
+    pub coalesced: u64,
+    // 掉线期间因队列满而丢弃的事件
+    pub dropped: u64,
+    // 因消费过慢被断开的次数
+    pub overflows: u64,
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    events: VecDeque<Event>,
+    // 消费过慢被断开，接收方下次 recv 返回 None
+    overflowed: bool,
+    // 发送方已经全部释放
+    closed: bool,
+    stats: OutboxStats,
+}
+
+#[derive(Debug)]
+struct Shared {
+    queue: Mutex<Queue>,
+    notify: Notify,
+    capacity: usize,
+}
+
+// dispatcher 一侧：推送永不等待，慢消费者不会拖住整个房间
+#[derive(Debug)]
+pub struct OutboxSender {
+    shared: Arc<Shared>,
+}
+
+// 连接或机器人一侧，用法与 mpsc::Receiver 相同
+#[derive(Debug)]
+pub struct OutboxReceiver {
+    shared: Arc<Shared>,
+}
+
+pub fn outbox(capacity: usize) -> (OutboxSender, OutboxReceiver) {
+    let capacity = capacity.max(1);
+    let mut queue = Queue::default();
+    queue.stats.capacity = capacity;
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(queue),
+        notify: Notify::new(),
+        capacity,
+    });
+    (
+        OutboxSender { shared: shared.clone() },
+        OutboxReceiver { shared },
+    )
+}
+
+// 盘口更新按 (花色, 方向) 合并，后来的覆盖先到的
+fn book_key(event: &Event) -> Option<(Suit, Side)> {
+    match event {
+        Event::QuotePlaced { quote } | Event::QuoteCanceled { quote } => Some((quote.suit, quote.side)),
+        _ => None,
+    }
+}
+
+// 推送结果，dispatcher 据此决定是否记日志
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Push {
+    Queued,
+    Coalesced,
+    Dropped,
+    Overflowed,
+}
+
+impl OutboxSender {
+    // online 为 false 表示当前没有连接在消费：队列满时只丢事件，不断开
+    pub fn push(&self, event: Event, online: bool) -> Push {
+        let capacity = self.shared.capacity;
+        let mut queue = self.shared.queue.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut outcome = Push::Queued;
+        if queue.events.len() >= capacity / COALESCE_RATIO
+            && let Some(key) = book_key(&event)
+            && let Some(idx) = queue.events.iter().rposition(|e| book_key(e) == Some(key))
+        {
+            queue.events.remove(idx);
+            queue.stats.coalesced += 1;
+            outcome = Push::Coalesced;
+        }
+
+        if queue.events.len() >= capacity {
+            if online {
+                // 断开慢消费者，重连后从空队列开始
+                queue.events.clear();
+                queue.overflowed = true;
+                queue.stats.overflows += 1;
+                queue.stats.depth = 0;
+                drop(queue);
+                self.shared.notify.notify_one();
+                return Push::Overflowed;
+            }
+            queue.stats.dropped += 1;
+            return Push::Dropped;
+        }
+
+        queue.events.push_back(event);
+        queue.stats.depth = queue.events.len();
+        queue.stats.high_water = queue.stats.high_water.max(queue.stats.depth);
+        drop(queue);
+        self.shared.notify.notify_one();
+        outcome
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    pub fn stats(&self) -> OutboxStats {
+        self.shared.queue.lock().unwrap_or_else(|e| e.into_inner()).stats
+    }
+}
+
+impl Drop for OutboxSender {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap_or_else(|e| e.into_inner()).closed = true;
+        self.shared.notify.notify_one();
+    }
+}
+
+impl OutboxReceiver {
+    // dispatcher 已经换掉或释放了这个队列，座位不能再用它接入
+    pub fn is_closed(&self) -> bool {
+        self.shared.queue.lock().unwrap_or_else(|e| e.into_inner()).closed
+    }
+
+    // 队列为空时等待；被判定为慢消费者或 dispatcher 已结束时返回 None
+    pub async fn recv(&mut self) -> Option<Event> {
+        loop {
+            {
+                let mut queue = self.shared.queue.lock().unwrap_or_else(|e| e.into_inner());
+                if queue.overflowed {
+                    queue.overflowed = false;
+                    return None;
+                }
+                if let Some(event) = queue.events.pop_front() {
+                    queue.stats.depth = queue.events.len();
+                    return Some(event);
+                }
+                if queue.closed {
+                    return None;
+                }
+            }
+            // 只有一个接收方，notify_one 没有等待者时会保留一个许可，不会丢唤醒
+            self.shared.notify.notified().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use figgie_core::Quote;
+
+    fn placed(suit: Suit, side: Side, price: u32) -> Event {
+        Event::QuotePlaced {
+            quote: Quote {
+                player_id: "p1".to_string(),
+                suit,
+                side,
+                price,
+            },
+        }
+    }
+
+    fn trade(price: u32) -> Event {
+        Event::TradeExecuted {
+            buyer: "p1".to_string(),
+            seller: "p2".to_string(),
+            suit: Suit::Heart,
+            price,
+        }
+    }
+
+    #[tokio::test]
+    async fn delivers_in_order() {
+        let (tx, mut rx) = outbox(8);
+        assert_eq!(tx.push(trade(1), true), Push::Queued);
+        assert_eq!(tx.push(placed(Suit::Club, Side::Bid, 2), true), Push::Queued);
+        assert!(matches!(rx.recv().await, Some(Event::TradeExecuted { price: 1, .. })));
+        assert!(matches!(rx.recv().await, Some(Event::QuotePlaced { .. })));
+
+        drop(tx);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn coalesces_book_updates_when_behind() {
+        let (tx, mut rx) = outbox(4);
+        tx.push(placed(Suit::Club, Side::Bid, 1), true);
+        tx.push(trade(5), true);
+        assert_eq!(tx.push(placed(Suit::Club, Side::Bid, 2), true), Push::Coalesced);
+        assert_eq!(tx.push(placed(Suit::Club, Side::Offer, 9), true), Push::Queued);
+        assert_eq!(tx.stats().depth, 3);
+
+        // 成交不会被合并，且顺序不变
+        assert!(matches!(rx.recv().await, Some(Event::TradeExecuted { .. })));
+        assert!(matches!(rx.recv().await, Some(Event::QuotePlaced { quote }) if quote.price == 2));
+    }
+
+    #[tokio::test]
+    async fn disconnects_slow_consumer() {
+        let (tx, mut rx) = outbox(2);
+        tx.push(trade(1), true);
+        tx.push(trade(2), true);
+        assert_eq!(tx.push(trade(3), true), Push::Overflowed);
+        assert!(rx.recv().await.is_none());
+        assert_eq!(tx.stats().overflows, 1);
+
+        // 重新连接后队列可以继续使用
+        tx.push(trade(4), true);
+        assert!(matches!(rx.recv().await, Some(Event::TradeExecuted { price: 4, .. })));
+    }
+
+    #[test]
+    fn drops_when_offline_queue_is_full() {
+        let (tx, _rx) = outbox(1);
+        tx.push(trade(1), false);
+        assert_eq!(tx.push(trade(2), false), Push::Dropped);
+        let stats = tx.stats();
+        assert_eq!((stats.depth, stats.dropped, stats.overflows), (1, 1, 0));
+    }
+}
@@ -0,0 +1,323 @@
+use crate::types::ActionView;
+use crate::dispatcher::{ActionRequest, Command};
+use crate::adapter::client_message_from_msgpack;
+use figgie_core::{Action, Event, GameConfig};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use schemars::{JsonSchema, schema_for};
+use tokio::sync::{mpsc::Sender, oneshot};
+
+// 协议有不兼容的改动时递增
+pub const PROTOCOL_VERSION: u32 = 1;
+pub const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// 客户端 → 服务端：{"v": 1, "id": "42", "type": "PlaceQuote", "payload": {...}}
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ClientMessage {
+    // 协议版本，缺省时按当前版本处理，兼容不带信封的旧客户端
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub v: Option<u32>,
+    // 客户端自定义的请求 id，服务端在 Ack/Nack 中原样带回
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub action: ActionView,
+}
+
+// 服务端发出的非游戏事件消息
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", content = "payload")]
+pub enum ControlMessage {
+    // 连接建立后的第一条消息
+    Hello {
+        protocol_version: u32,
+        server_version: String,
+        player_id: String,
+        room: GameConfig,
+    },
+    // 请求已被引擎接受
+    Ack {
+        id: String,
+    },
+    // 请求被拒绝；无法解析的消息 id 为空
+    Nack {
+        id: Option<String>,
+        code: String,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ServerBody {
+    Control(ControlMessage),
+    Event(Event),
+}
+
+// 服务端 → 客户端：{"v": 1, "type": "...", "payload": {...}}
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ServerMessage {
+    pub v: u32,
+    #[serde(flatten)]
+    pub body: ServerBody,
+}
+
+impl From<ControlMessage> for ServerMessage {
+    fn from(message: ControlMessage) -> Self {
+        ServerMessage {
+            v: PROTOCOL_VERSION,
+            body: ServerBody::Control(message),
+        }
+    }
+}
+
+impl From<Event> for ServerMessage {
+    fn from(event: Event) -> Self {
+        ServerMessage {
+            v: PROTOCOL_VERSION,
+            body: ServerBody::Event(event),
+        }
+    }
+}
+
+pub fn hello(player_id: &str, room: &GameConfig) -> ControlMessage {
+    ControlMessage::Hello {
+        protocol_version: PROTOCOL_VERSION,
+        server_version: SERVER_VERSION.to_string(),
+        player_id: player_id.to_string(),
+        room: room.clone(),
+    }
+}
+
+pub fn nack(id: Option<String>, code: &str, reason: impl ToString) -> ControlMessage {
+    ControlMessage::Nack {
+        id,
+        code: code.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+// 导出协议的 JSON Schema，供机器人等外部客户端生成代码或校验消息
+pub fn schema() -> Value {
+    json!({
+        "protocol_version": PROTOCOL_VERSION,
+        "client": schema_for!(ClientMessage),
+        "server": schema_for!(ServerMessage),
+    })
+}
+
+// 处理一条客户端文本消息：解析、校验后交给 dispatcher，返回需要回给客户端的 Ack/Nack
+// controls 为 true 时允许暂停、恢复和结束对局（房间创建者或管理员）
+pub async fn handle_client_text(
+    text: &str,
+    player_id: &str,
+    controls: bool,
+    action_sender: &Sender<Command>,
+) -> Option<ControlMessage> {
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(message) => message,
+        Err(e) => {
+            let id = request_id(serde_json::from_str::<Value>(text).ok());
+            return Some(nack(id, "MalformedMessage", e));
+        }
+    };
+    dispatch_client_message(message, player_id, controls, action_sender).await
+}
+
+// MessagePack 编码的客户端消息，结构与 JSON 相同
+pub async fn handle_client_binary(
+    bytes: &[u8],
+    player_id: &str,
+    controls: bool,
+    action_sender: &Sender<Command>,
+) -> Option<ControlMessage> {
+    let message = match client_message_from_msgpack(bytes) {
+        Ok(message) => message,
+        Err(e) => {
+            let id = request_id(rmp_serde::from_slice::<Value>(bytes).ok());
+            return Some(nack(id, "MalformedMessage", e));
+        }
+    };
+    dispatch_client_message(message, player_id, controls, action_sender).await
+}
+
+// 解析失败时尽量带回 id，方便客户端对应
+fn request_id(value: Option<Value>) -> Option<String> {
+    value.and_then(|v| v.get("id").and_then(Value::as_str).map(str::to_string))
+}
+
+// 被限流的消息不交给 dispatcher，只取出 id 用于 Nack
+pub fn text_request_id(text: &str) -> Option<String> {
+    request_id(serde_json::from_str::<Value>(text).ok())
+}
+
+pub fn binary_request_id(bytes: &[u8]) -> Option<String> {
+    request_id(rmp_serde::from_slice::<Value>(bytes).ok())
+}
+
+async fn dispatch_client_message(
+    message: ClientMessage,
+    player_id: &str,
+    controls: bool,
+    action_sender: &Sender<Command>,
+) -> Option<ControlMessage> {
+    let ClientMessage { v, id, action } = message;
+    if let Some(v) = v
+        && v != PROTOCOL_VERSION {
+        return Some(nack(id, "UnsupportedVersion", format!(
+            "protocol version {v} is not supported, server speaks {PROTOCOL_VERSION}"
+        )));
+    }
+
+    let action = Action::from(action);
+    if let Some(actor) = action.player_id()
+        && actor != player_id {
+        return Some(nack(id, "Forbidden", format!("cannot act on behalf of {actor}")));
+    }
+    // 回合由服务端按时结算，提前结束回合和暂停一样只对创建者和管理员开放
+    if !controls && matches!(action, Action::Pause | Action::Resume | Action::EndRound | Action::EndGame) {
+        return Some(nack(id, "Forbidden", "only the room creator or an admin can pause, resume or end the round or game"));
+    }
+
+    let (reply_sender, reply_receiver) = oneshot::channel();
+    let request = ActionRequest {
+        action,
+        reply: Some(reply_sender),
+        by_admin: false,
+    };
+    if action_sender.send(Command::Action(request)).await.is_err() {
+        return Some(nack(id, "RoomClosed", "room is no longer running"));
+    }
+
+    match reply_receiver.await {
+        Ok(Ok(())) => id.map(|id| ControlMessage::Ack { id }),
+        Ok(Err(e)) => Some(nack(id, e.code(), e)),
+        Err(_) => Some(nack(id, "RoomClosed", "room is no longer running")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use figgie_core::{FiggieError, Quote, Side, Suit};
+    use tokio::sync::mpsc::channel;
+
+    fn place_quote_json(extra: &str) -> String {
+        format!(
+            r#"{{{extra}"type":"PlaceQuote","payload":{{"player_id":"p1","suit":"Heart","side":"Bid","price":7}}}}"#
+        )
+    }
+
+    #[test]
+    fn parses_legacy_and_enveloped_actions() {
+        let legacy: ClientMessage = serde_json::from_str(&place_quote_json("")).unwrap();
+        assert_eq!(legacy.v, None);
+        assert_eq!(legacy.id, None);
+
+        let enveloped: ClientMessage = serde_json::from_str(&place_quote_json(r#""v":1,"id":"42","#)).unwrap();
+        assert_eq!(enveloped.v, Some(1));
+        assert_eq!(enveloped.id.as_deref(), Some("42"));
+        assert!(matches!(enveloped.action, ActionView::PlaceQuote { suit: Suit::Heart, side: Side::Bid, price: 7, .. }));
+    }
+
+    #[test]
+    fn events_keep_type_payload_shape() {
+        let event = Event::QuotePlaced {
+            quote: Quote {
+                player_id: "p1".to_string(),
+                suit: Suit::Club,
+                side: Side::Offer,
+                price: 9,
+            },
+        };
+        let value = serde_json::to_value(ServerMessage::from(event)).unwrap();
+        assert_eq!(value, json!({
+            "v": PROTOCOL_VERSION,
+            "type": "QuotePlaced",
+            "payload": {"quote": {"player_id": "p1", "suit": "Club", "side": "Offer", "price": 9}}
+        }));
+
+        let ack = serde_json::to_value(ServerMessage::from(ControlMessage::Ack { id: "7".to_string() })).unwrap();
+        assert_eq!(ack, json!({"v": PROTOCOL_VERSION, "type": "Ack", "payload": {"id": "7"}}));
+    }
+
+    #[test]
+    fn schema_covers_both_directions() {
+        let schema = schema();
+        let text = schema.to_string();
+        assert_eq!(schema["protocol_version"], json!(PROTOCOL_VERSION));
+        for name in ["PlaceQuote", "Hello", "Nack", "RoundEnded", "TradeExecuted"] {
+            assert!(text.contains(name), "schema is missing {name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_messages_before_dispatch() {
+        let (tx, mut rx) = channel(4);
+
+        let reply = handle_client_text(r#"{"id":"1","type":"Nope"}"#, "p1", false, &tx).await;
+        assert!(matches!(reply, Some(ControlMessage::Nack { id: Some(id), code, .. }) if id == "1" && code == "MalformedMessage"));
+
+        let reply = handle_client_text(&place_quote_json(r#""v":99,"id":"2","#), "p1", false, &tx).await;
+        assert!(matches!(reply, Some(ControlMessage::Nack { code, .. }) if code == "UnsupportedVersion"));
+
+        let reply = handle_client_text(&place_quote_json(r#""id":"3","#), "p2", false, &tx).await;
+        assert!(matches!(reply, Some(ControlMessage::Nack { code, .. }) if code == "Forbidden"));
+
+        assert!(rx.try_recv().is_err());
+    }
+
+    // 暂停、恢复和结束对局只对房间创建者和管理员的连接开放
+    #[tokio::test]
+    async fn game_controls_require_creator_or_admin() {
+        let (tx, mut rx) = channel(4);
+        let controls = [
+            r#"{"id":"1","type":"Pause","payload":{"room_id":"R1"}}"#,
+            r#"{"id":"2","type":"Resume","payload":{"room_id":"R1"}}"#,
+            r#"{"id":"4","type":"EndRound","payload":{"round_id":1,"room_id":"R1"}}"#,
+            r#"{"id":"3","type":"EndGame","payload":{"round_id":1,"room_id":"R1","player_id":"p1"}}"#,
+        ];
+        for text in controls {
+            let reply = handle_client_text(text, "p1", false, &tx).await;
+            assert!(matches!(reply, Some(ControlMessage::Nack { code, .. }) if code == "Forbidden"));
+        }
+        assert!(rx.try_recv().is_err());
+
+        for text in controls {
+            let reply = tokio::spawn({
+                let tx = tx.clone();
+                async move { handle_client_text(text, "p1", true, &tx).await }
+            });
+            let Some(Command::Action(request)) = rx.recv().await else {
+                panic!("expected an action");
+            };
+            assert!(matches!(request.action, Action::Pause | Action::Resume | Action::EndRound | Action::EndGame));
+            request.reply.unwrap().send(Ok(())).unwrap();
+            assert!(matches!(reply.await.unwrap(), Some(ControlMessage::Ack { .. })));
+        }
+    }
+
+    #[tokio::test]
+    async fn acks_and_nacks_reference_request_id() {
+        let (tx, mut rx) = channel::<Command>(4);
+        tokio::spawn(async move {
+            let mut accept = true;
+            while let Some(Command::Action(request)) = rx.recv().await {
+                let outcome = if accept { Ok(()) } else { Err(FiggieError::QuoteNotCompetitive.into()) };
+                let _ = request.reply.unwrap().send(outcome);
+                accept = !accept;
+            }
+        });
+
+        let reply = handle_client_text(&place_quote_json(r#""v":1,"id":"a","#), "p1", false, &tx).await;
+        assert!(matches!(reply, Some(ControlMessage::Ack { id }) if id == "a"));
+
+        let reply = handle_client_text(&place_quote_json(r#""v":1,"id":"b","#), "p1", false, &tx).await;
+        assert!(matches!(reply, Some(ControlMessage::Nack { id: Some(id), code, .. }) if id == "b" && code == "QuoteNotCompetitive"));
+
+        // 旧客户端不带 id，成功时不回 Ack
+        let reply = handle_client_text(&place_quote_json(""), "p1", false, &tx).await;
+        assert!(reply.is_none());
+    }
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+
+// 新玩家的初始分
+pub const INITIAL_RATING: f64 = 1500.0;
+
+// 一局内分数变化的上限，多人局按对手数平摊
+const K_FACTOR: f64 = 32.0;
+
+// 只有真人的桌子和有机器人的桌子分开计分
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ladder {
+    #[default]
+    Human,
+    Mixed,
+}
+
+impl Ladder {
+    pub fn for_table(mut is_robot: impl Iterator<Item = bool>) -> Self {
+        if is_robot.any(|robot| robot) { Ladder::Mixed } else { Ladder::Human }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Ladder::Human => "human",
+            Ladder::Mixed => "mixed",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+    #[default]
+    All,
+}
+
+impl Period {
+    // 统计区间的起点（毫秒时间戳）
+    pub fn since_millis(&self, now_millis: u64) -> u64 {
+        const DAY: u64 = 24 * 3600 * 1000;
+        let span = match self {
+            Period::Day => DAY,
+            Period::Week => 7 * DAY,
+            Period::Month => 30 * DAY,
+            Period::All => return 0,
+        };
+        now_millis.saturating_sub(span)
+    }
+}
+
+// 多人 Elo：把一局拆成两两对局，名次靠前算赢，名次相同算平
+// 输入 (当前分, 名次)，返回新分数，所有人的变化之和为 0
+pub fn update(players: &[(f64, u32)]) -> Vec<f64> {
+    if players.len() < 2 {
+        return players.iter().map(|(rating, _)| *rating).collect();
+    }
+    let k = K_FACTOR / (players.len() - 1) as f64;
+    players
+        .iter()
+        .enumerate()
+        .map(|(i, (rating, rank))| {
+            let delta: f64 = players
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, (other, other_rank))| {
+                    let expected = 1.0 / (1.0 + 10f64.powf((other - rating) / 400.0));
+                    let actual = match rank.cmp(other_rank) {
+                        std::cmp::Ordering::Less => 1.0,
+                        std::cmp::Ordering::Equal => 0.5,
+                        std::cmp::Ordering::Greater => 0.0,
+                    };
+                    actual - expected
+                })
+                .sum();
+            rating + k * delta
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn winners_gain_and_total_is_conserved() {
+        let before = [(1500.0, 1), (1500.0, 2), (1500.0, 3), (1500.0, 4)];
+        let after = update(&before);
+        assert!(after[0] > after[1] && after[1] > after[2] && after[2] > after[3]);
+        assert!((after.iter().sum::<f64>() - 6000.0).abs() < 1e-9);
+        // 第一名赢下全部三场对局
+        assert!((after[0] - 1516.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn upsets_move_ratings_more() {
+        let expected = update(&[(1800.0, 1), (1400.0, 2)]);
+        let upset = update(&[(1800.0, 2), (1400.0, 1)]);
+        assert!(upset[1] - 1400.0 > expected[0] - 1800.0);
+
+        let tied = update(&[(1500.0, 1), (1500.0, 1)]);
+        assert_eq!(tied, vec![1500.0, 1500.0]);
+    }
+
+    #[test]
+    fn ladders_and_periods() {
+        assert_eq!(Ladder::for_table([false, false].into_iter()), Ladder::Human);
+        assert_eq!(Ladder::for_table([false, true].into_iter()), Ladder::Mixed);
+        assert_eq!(Period::All.since_millis(1_000), 0);
+        assert_eq!(Period::Day.since_millis(100_000_000), 100_000_000 - 86_400_000);
+    }
+}
@@ -1,22 +1,47 @@
 use tokio::select;
-use tokio::time::{sleep_until, Duration, Instant};
+use tokio::time::{interval, Duration, MissedTickBehavior};
 use rand::{SeedableRng, rngs::SmallRng, Rng};
 use figgie_core::*;
-use tokio::sync::mpsc::*;
-use log;
+use crate::dispatcher::Participant;
+use crate::metrics::{action_label, METRICS};
+use serde::Deserialize;
 
-const PAUSE_SECONDS: u64 = 3;
-const RANDOM_DELAY_LOWER_BOUND: u64 = 2;
-const RANDOM_DELAY_UPPER_BOUND: u64 = 8;
-const EXECUTE_QUOTE_POSSIBILITY: f64 = 0.2;
-const RANDOM_PRICE_LOWER_BOUND: u32 = 3;
-const RANDOM_PRICE_UPPER_BOUND: u32 = 18;
+// 机器人行为参数，可在配置文件 [robots] 中调整
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct RobotParams {
+    pub pause_secs: u64,
+    // 机器人按该间隔检查时钟，决定是否行动
+    pub tick_millis: u64,
+    // 两次行动之间的随机间隔
+    pub delay_min_secs: u64,
+    pub delay_max_secs: u64,
+    // 直接成交已有挂单的概率
+    pub execute_quote_probability: f64,
+    pub price_min: u32,
+    pub price_max: u32,
+}
+
+impl Default for RobotParams {
+    fn default() -> Self {
+        RobotParams {
+            pause_secs: 3,
+            tick_millis: 100,
+            delay_min_secs: 2,
+            delay_max_secs: 8,
+            execute_quote_probability: 0.2,
+            price_min: 3,
+            price_max: 18,
+        }
+    }
+}
 
 struct RobotState {
     id: String,
     hand: Hand,
     quotes: Vec<Quote>,
     cash: i32,
+    pot: i32,
 }
 
 enum LoopControl {
@@ -27,11 +52,12 @@ enum LoopControl {
 }
 
 impl RobotState {
-    pub fn new(id: String, hand: Hand, cash: i32) -> Self {
+    pub fn new(id: String, hand: Hand, cash: i32, pot: u32) -> Self {
         Self {
             id,
             hand,
             cash,
+            pot: pot as i32,
             quotes: Vec::new(),
         }
     }
@@ -60,39 +86,39 @@ fn apply_event(state: &mut RobotState, event: Event) -> LoopControl {
             state.quotes.push(quote);
             LoopControl::Continue
         }
-        Event::RoundStarted { round_id, server_time, player } => {
+        Event::RoundStarted { player, .. } => {
             state.hand = player.hand;
             state.cash = player.cash;
             state.quotes.clear();
             LoopControl::Start
         }
-        Event::RoundEnded { round_id, players, server_time, goal_suit } => {
-            let engine_player = players
-                .iter()
-                .find(|p| p.info.id == state.id)
-                .expect("robot player not found in RoundEnded");
-            
+        Event::RoundEnded { players, goal_suit, .. } => {
+            let Some(engine_player) = players.iter().find(|p| p.info.id == state.id) else {
+                log::error!("robot {} not found in RoundEnded", state.id);
+                state.quotes.clear();
+                return LoopControl::Pause;
+            };
+
+            // 本地记账与引擎不一致时只记录日志，随后以引擎为准
             for (suit, count) in &engine_player.hand.cards {
                 let local = state.hand.cards.get(suit).copied().unwrap_or(0);
-                assert!(
-                    local == *count,
-                    "hand mismatch for {:?}: local={}, engine={}",
-                    suit,
-                    local,
-                    count
-                );
+                if local != *count {
+                    log::error!(
+                        "robot {} hand mismatch for {:?}: local={}, engine={}",
+                        state.id, suit, local, count
+                    );
+                }
             }
 
             // 反向检查：本地有但引擎没有的花色，一般来说不存在这种情况
             for (suit, count) in &state.hand.cards {
                 let engine = engine_player.hand.cards.get(suit).copied().unwrap_or(0);
-                assert!(
-                    engine == *count,
-                    "hand mismatch for {:?}: local={}, engine={}",
-                    suit,
-                    count,
-                    engine
-                );
+                if engine != *count {
+                    log::error!(
+                        "robot {} hand mismatch for {:?}: local={}, engine={}",
+                        state.id, suit, count, engine
+                    );
+                }
             }
 
             let cash1 = state.cash;
@@ -108,29 +134,19 @@ fn apply_event(state: &mut RobotState, event: Event) -> LoopControl {
 
             let winner_count = goal_counts.iter().filter(|&&c| c == max_goal).count() as i32;
 
-            if my_goal_count == max_goal {
-                let total_goal_cards: u8 = goal_counts.iter().copied().sum();
-
-                let bonus = match total_goal_cards {
-                    10 => 100,
-                    8 => 120,
-                    _ => {
-                        log::warn!(
-                            "unexpected total goal suit count: {}",
-                            total_goal_cards
-                        );
-                        0
-                    }
-                };
+            if max_goal > 0 && my_goal_count == max_goal {
+                // 余牌被弃掉时场上的 goal 牌可能少于发牌数，剩余底池以实际持有数为准
+                let total_goal_cards: i32 = goal_counts.iter().map(|&c| c as i32).sum();
+                let bonus = (state.pot - total_goal_cards * CARD_VALUE_PER_GOAL_SUIT).max(0);
                 cash2 += bonus / winner_count;
             }
 
-            assert!(
-                cash2 == engine_player.cash,
-                "cash mismatch: local_calc={}, engine={}",
-                cash2,
-                engine_player.cash
-            );
+            if cash2 != engine_player.cash {
+                log::error!(
+                    "robot {} cash mismatch: local_calc={}, engine={}",
+                    state.id, cash2, engine_player.cash
+                );
+            }
 
             state.cash = engine_player.cash;
             state.hand = engine_player.hand.clone();
@@ -143,30 +159,45 @@ fn apply_event(state: &mut RobotState, event: Event) -> LoopControl {
             });
             LoopControl::Continue
         }
-        Event::GameEnded { players } => {
+        Event::GameEnded { .. } | Event::ServerShutdown { .. } => {
             state.quotes.clear();
             LoopControl::Break
         }
-        _ => LoopControl::Continue
+        // 暂停期间的挂单会被拒绝，恢复后继续
+        Event::TradingPaused { .. } => LoopControl::Pause,
+        Event::TradingResumed { .. } => LoopControl::Start,
+        Event::PlayerDisconnected { .. } | Event::PlayerReconnected { .. } | Event::ChatMessage { .. } => LoopControl::Continue,
     }
 }
 
 pub async fn robot_loop(
-    player_id: String,
+    participant: Participant,
     hand: Hand,
     cash: i32,
-    mut event_rx: Receiver<Event>,
-    action_tx: Sender<Action>,
+    clock: SharedClock,
+    params: RobotParams,
 ) {
-    println!("Robot loop starting...");
+    let Participant {
+        player_id,
+        room,
+        action_sender: action_tx,
+        event_receiver: mut event_rx,
+        ..
+    } = participant;
+    let pot = room.pot;
+    log::info!("robot {} loop starting", player_id);
     let mut rng = SmallRng::from_entropy();
-    let mut state = RobotState::new(player_id, hand, cash);
-    let mut next_action_at = Instant::now() + random_delay(&mut rng);
+    let mut state = RobotState::new(player_id, hand, cash, pot);
+    let mut next_action_at = clock.now() + random_delay(&params, &mut rng);
     let mut paused = false;
+    let mut ticker = interval(Duration::from_millis(params.tick_millis.max(1)));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
     loop {
         select! {
-            Some(event) = event_rx.recv() => {
+            event = event_rx.recv() => {
+                // 座位被换掉或房间已结束
+                let Some(event) = event else { break };
                 match apply_event(&mut state, event) {
                     LoopControl::Continue => {},
                     LoopControl::Pause => { paused = true },
@@ -175,28 +206,34 @@ pub async fn robot_loop(
                 }
             }
 
-            _ = sleep_until(next_action_at) => {
+            _ = ticker.tick() => {
+                let now = clock.now();
+                if now < next_action_at {
+                    continue;
+                }
                 if paused {
                     // 不发 action，但要重置下一次 wakeup
-                    next_action_at = Instant::now() + Duration::from_secs(PAUSE_SECONDS);
+                    next_action_at = now + Duration::from_secs(params.pause_secs);
                     continue;
                 }
-                let action = decide_action(&state, &mut rng);
-                let _ = action_tx.send(action).await;
+                let action = decide_action(&state, &params, &mut rng);
+                METRICS.robot_actions.with_label_values(&[action_label(&action)]).inc();
+                let _ = action_tx.send(action.into()).await;
 
-                next_action_at = Instant::now() + random_delay(&mut rng);
+                next_action_at = now + random_delay(&params, &mut rng);
             }
         }
     }
 
-    println!("Robot loop ended...");
+    log::info!("robot {} loop ended", state.id);
 }
 
-fn random_delay(rng: &mut impl Rng) -> Duration {
-    Duration::from_secs(rng.gen_range(RANDOM_DELAY_LOWER_BOUND..=RANDOM_DELAY_UPPER_BOUND))
+fn random_delay(params: &RobotParams, rng: &mut impl Rng) -> Duration {
+    let max = params.delay_max_secs.max(params.delay_min_secs);
+    Duration::from_secs(rng.gen_range(params.delay_min_secs..=max))
 }
 
-fn decide_action(state: &RobotState, rng: &mut impl Rng) -> Action {
+fn decide_action(state: &RobotState, params: &RobotParams, rng: &mut impl Rng) -> Action {
     let hittable_quotes: Vec<&Quote> = state
         .quotes
         .iter()
@@ -206,7 +243,7 @@ fn decide_action(state: &RobotState, rng: &mut impl Rng) -> Action {
     let robot_id = state.id.clone();
     
     // 按概率去成交已有 quote
-    if !hittable_quotes.is_empty() && rng.gen_bool(EXECUTE_QUOTE_POSSIBILITY) {
+    if !hittable_quotes.is_empty() && rng.gen_bool(params.execute_quote_probability.clamp(0.0, 1.0)) {
         let quote = hittable_quotes[rng.gen_range(0..hittable_quotes.len())];
 
         let hit_quote = Quote {
@@ -226,7 +263,7 @@ fn decide_action(state: &RobotState, rng: &mut impl Rng) -> Action {
         player_id: robot_id,
         suit: random_suit(),
         side: if rng.gen_bool(0.5) { Side::Bid } else { Side::Offer },
-        price: rng.gen_range(RANDOM_PRICE_LOWER_BOUND..=RANDOM_PRICE_UPPER_BOUND),
+        price: rng.gen_range(params.price_min..=params.price_max.max(params.price_min)),
     })
 }
 
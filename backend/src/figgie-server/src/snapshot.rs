@@ -0,0 +1,151 @@
+use crate::dispatcher::AbsentQuotePolicy;
+use crate::limits::FloodLimits;
+
+use std::path::{Path, PathBuf};
+use figgie_core::GameSnapshot;
+use serde::{Deserialize, Serialize};
+
+// 停机时每个房间写一个文件：<data_dir>/rooms/<room_id>.json
+const ROOMS_DIR: &str = "rooms";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoomSnapshot {
+    pub room_id: String,
+    // 对局记录的 id，恢复后继续写入同一局
+    #[serde(default)]
+    pub game_id: Option<i64>,
+    pub absent_quote_policy: AbsentQuotePolicy,
+    #[serde(default)]
+    pub limits: FloodLimits,
+    #[serde(default)]
+    pub creator: Option<String>,
+    pub game: GameSnapshot,
+}
+
+fn rooms_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(ROOMS_DIR)
+}
+
+// room_id 来自客户端：安全字符原样保留，其余字节按 %XX 编码，不同的 room_id 不会撞到同一个文件
+fn file_name(room_id: &str) -> String {
+    let mut safe = String::with_capacity(room_id.len());
+    for byte in room_id.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            safe.push(byte as char);
+        } else {
+            safe.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    format!("{safe}.json")
+}
+
+pub fn save(data_dir: &Path, snapshot: &RoomSnapshot) -> std::io::Result<PathBuf> {
+    let dir = rooms_dir(data_dir);
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(file_name(&snapshot.room_id));
+    let text = serde_json::to_string_pretty(snapshot).map_err(std::io::Error::other)?;
+    // 先写临时文件再改名，避免停机过程中留下半个文件
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, text)?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(path)
+}
+
+// 读出所有快照和对应的文件路径；文件要等房间恢复成功后用 remove 删除
+pub fn load_all(data_dir: &Path) -> Vec<(PathBuf, RoomSnapshot)> {
+    let Ok(entries) = std::fs::read_dir(rooms_dir(data_dir)) else {
+        return vec![];
+    };
+
+    let mut snapshots = vec![];
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| serde_json::from_str::<RoomSnapshot>(&text).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(snapshot) => snapshots.push((path, snapshot)),
+            Err(e) => {
+                log::error!("failed to read snapshot {}: {}", path.display(), e);
+                mark_failed(&path);
+            }
+        }
+    }
+    snapshots
+}
+
+// 房间已经恢复，快照不再需要
+pub fn remove(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        log::warn!("failed to remove snapshot {}: {}", path.display(), e);
+    }
+}
+
+// 恢复失败的快照改名为 .failed 留给人工处理，下次启动不会再读
+pub fn mark_failed(path: &Path) {
+    let failed = path.with_extension("json.failed");
+    if let Err(e) = std::fs::rename(path, &failed) {
+        log::warn!("failed to rename snapshot {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::game;
+    use figgie_core::*;
+
+    fn snapshot(room_id: &str) -> RoomSnapshot {
+        RoomSnapshot {
+            room_id: room_id.to_string(),
+            game_id: Some(3),
+            absent_quote_policy: AbsentQuotePolicy::CancelOnDisconnect,
+            limits: FloodLimits::default(),
+            creator: Some("p0".to_string()),
+            game: game(room_id).snapshot(),
+        }
+    }
+
+    #[test]
+    fn saved_rooms_stay_until_restored() {
+        let dir = std::env::temp_dir().join(format!("figgie-snapshot-{}", std::process::id()));
+        let path = save(&dir, &snapshot("R/1")).unwrap();
+        assert_eq!(path.file_name().unwrap(), "R%2F1.json");
+
+        let restored = load_all(&dir);
+        assert_eq!(restored.len(), 1);
+        let (path, restored) = &restored[0];
+        assert_eq!(restored.room_id, "R/1");
+        assert_eq!(restored.absent_quote_policy, AbsentQuotePolicy::CancelOnDisconnect);
+        assert_eq!(restored.creator.as_deref(), Some("p0"));
+        assert_eq!(restored.game.phase, Phase::Trading);
+        // 恢复成功之前文件一直保留
+        assert_eq!(load_all(&dir).len(), 1);
+        remove(path);
+        assert!(load_all(&dir).is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn similar_room_ids_do_not_share_a_file() {
+        let dir = std::env::temp_dir().join(format!("figgie-snapshot-ids-{}", std::process::id()));
+        for room_id in ["a.b", "a/b", "a_b", "a%2Eb"] {
+            save(&dir, &snapshot(room_id)).unwrap();
+        }
+        let mut ids: Vec<String> = load_all(&dir).into_iter().map(|(_, s)| s.room_id).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["a%2Eb", "a.b", "a/b", "a_b"]);
+
+        // 恢复失败的快照改名后不再读取
+        let (path, _) = load_all(&dir).into_iter().find(|(_, s)| s.room_id == "a.b").unwrap();
+        mark_failed(&path);
+        assert!(path.with_extension("json.failed").exists());
+        assert_eq!(load_all(&dir).len(), 3);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
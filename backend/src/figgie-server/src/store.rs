@@ -0,0 +1,770 @@
+use crate::rating::{self, Ladder, INITIAL_RATING};
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use figgie_core::{Event, GameConfig};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::Serialize;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+// 对局记录存放在 <data_dir>/figgie.db
+pub const DB_FILE: &str = "figgie.db";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS games (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    room_id TEXT NOT NULL,
+    room_name TEXT NOT NULL,
+    config TEXT NOT NULL,
+    rated INTEGER NOT NULL DEFAULT 1,
+    started_at INTEGER NOT NULL,
+    ended_at INTEGER
+);
+CREATE TABLE IF NOT EXISTS participants (
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    player_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    is_robot INTEGER NOT NULL,
+    seat INTEGER NOT NULL,
+    PRIMARY KEY (game_id, player_id)
+);
+CREATE INDEX IF NOT EXISTS participants_player ON participants(player_id);
+CREATE TABLE IF NOT EXISTS rounds (
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    round_id INTEGER NOT NULL,
+    goal_suit TEXT NOT NULL,
+    common_suit TEXT NOT NULL,
+    ended_at INTEGER NOT NULL,
+    PRIMARY KEY (game_id, round_id)
+);
+CREATE TABLE IF NOT EXISTS round_results (
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    round_id INTEGER NOT NULL,
+    player_id TEXT NOT NULL,
+    goal_cards INTEGER NOT NULL,
+    cash INTEGER NOT NULL,
+    PRIMARY KEY (game_id, round_id, player_id)
+);
+CREATE TABLE IF NOT EXISTS trades (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    round_id INTEGER NOT NULL,
+    buyer TEXT NOT NULL,
+    seller TEXT NOT NULL,
+    suit TEXT NOT NULL,
+    price INTEGER NOT NULL,
+    executed_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS trades_game ON trades(game_id);
+CREATE TABLE IF NOT EXISTS chat_messages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    round_id INTEGER NOT NULL,
+    sender TEXT NOT NULL,
+    recipient TEXT,
+    text TEXT NOT NULL,
+    sent_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS chat_messages_game ON chat_messages(game_id);
+CREATE TABLE IF NOT EXISTS standings (
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    player_id TEXT NOT NULL,
+    rank INTEGER NOT NULL,
+    cash INTEGER NOT NULL,
+    PRIMARY KEY (game_id, player_id)
+);
+CREATE TABLE IF NOT EXISTS users (
+    player_id TEXT PRIMARY KEY,
+    password_hash TEXT NOT NULL,
+    display_name TEXT NOT NULL,
+    avatar_url TEXT,
+    email TEXT,
+    created_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS ratings (
+    ladder TEXT NOT NULL,
+    player_id TEXT NOT NULL,
+    rating REAL NOT NULL,
+    games INTEGER NOT NULL,
+    PRIMARY KEY (ladder, player_id)
+);
+CREATE TABLE IF NOT EXISTS rating_changes (
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    ladder TEXT NOT NULL,
+    player_id TEXT NOT NULL,
+    players INTEGER NOT NULL,
+    before REAL NOT NULL,
+    after REAL NOT NULL,
+    at INTEGER NOT NULL,
+    PRIMARY KEY (game_id, player_id)
+);
+CREATE INDEX IF NOT EXISTS rating_changes_at ON rating_changes(ladder, at);
+";
+
+// dispatcher 交给写入线程的一批事件
+#[derive(Debug)]
+pub struct Record {
+    pub game_id: i64,
+    pub round: u32,
+    pub at_millis: u64,
+    pub events: Vec<Event>,
+}
+
+// dispatcher 持有的记录句柄；写库在后台线程进行，不阻塞撮合
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    pub game_id: i64,
+    sender: UnboundedSender<Record>,
+}
+
+impl Recorder {
+    pub fn new(game_id: i64, sender: UnboundedSender<Record>) -> Self {
+        Recorder { game_id, sender }
+    }
+
+    pub fn record(&self, round: u32, at_millis: u64, events: &[Event]) {
+        // 只关心成交、聊天和结算
+        let events: Vec<Event> = events
+            .iter()
+            .filter(|e| matches!(
+                e,
+                Event::TradeExecuted { .. } | Event::ChatMessage { .. } | Event::RoundEnded { .. } | Event::GameEnded { .. }
+            ))
+            .cloned()
+            .collect();
+        if events.is_empty() {
+            return;
+        }
+        let record = Record { game_id: self.game_id, round, at_millis, events };
+        if self.sender.send(record).is_err() {
+            log::error!("game recorder stopped, game id: {}", self.game_id);
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ParticipantRecord {
+    pub player_id: String,
+    pub name: String,
+    pub is_robot: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RoundResult {
+    pub player_id: String,
+    pub goal_cards: u32,
+    pub cash: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RoundRecord {
+    pub round_id: u32,
+    pub goal_suit: String,
+    pub common_suit: String,
+    pub ended_at: u64,
+    pub results: Vec<RoundResult>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TradeRecord {
+    pub round_id: u32,
+    pub buyer: String,
+    pub seller: String,
+    pub suit: String,
+    pub price: u32,
+    pub executed_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ChatRecord {
+    pub round_id: u32,
+    pub from: String,
+    // 私聊的接收者，全桌消息为空
+    pub to: Option<String>,
+    pub text: String,
+    pub sent_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Standing {
+    pub player_id: String,
+    pub rank: u32,
+    pub cash: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GameRecord {
+    pub id: i64,
+    pub room_id: String,
+    pub room_name: String,
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    pub players: Vec<ParticipantRecord>,
+    pub rounds: Vec<RoundRecord>,
+    pub trades: Vec<TradeRecord>,
+    pub chat: Vec<ChatRecord>,
+    // 游戏结束前为空
+    pub standings: Vec<Standing>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HistoryEntry {
+    pub game_id: i64,
+    pub room_id: String,
+    pub room_name: String,
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    pub players: u32,
+    // 游戏未结束时为空
+    pub rank: Option<u32>,
+    pub cash: Option<i64>,
+}
+
+// 账号的公开资料，email 只返回给本人
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Profile {
+    pub player_id: String,
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LeaderboardQuery {
+    pub ladder: Ladder,
+    // 只统计这个时间之后结束的对局
+    pub since_millis: u64,
+    // 只统计这个人数的对局
+    pub players: Option<u32>,
+    pub limit: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LeaderboardEntry {
+    pub rank: u32,
+    pub player_id: String,
+    pub display_name: String,
+    pub is_robot: bool,
+    pub rating: f64,
+    // 筛选范围内的对局数和分数变化
+    pub games: u32,
+    pub change: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct UserRecord {
+    pub password_hash: String,
+    pub profile: Profile,
+}
+
+#[derive(Clone, Debug)]
+pub struct Store {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")?;
+        conn.execute_batch(SCHEMA)?;
+        // 旧数据库没有 rated 列
+        let has_rated: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM pragma_table_info('games') WHERE name = 'rated')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_rated {
+            conn.execute_batch("ALTER TABLE games ADD COLUMN rated INTEGER NOT NULL DEFAULT 1")?;
+        }
+        Ok(Store { conn: Arc::new(Mutex::new(conn)) })
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // 启动后台写入线程，返回的 sender 由各房间的 Recorder 共用
+    pub fn spawn_writer(&self) -> UnboundedSender<Record> {
+        let (sender, mut receiver) = unbounded_channel::<Record>();
+        let store = self.clone();
+        std::thread::spawn(move || {
+            while let Some(record) = receiver.blocking_recv() {
+                if let Err(e) = store.write(&record) {
+                    log::error!("failed to record game {}: {}", record.game_id, e);
+                }
+            }
+        });
+        sender
+    }
+
+    pub fn create_game(&self, config: &GameConfig, started_at: u64, rated: bool) -> rusqlite::Result<i64> {
+        let config_json = serde_json::to_string(config).unwrap_or_default();
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO games (room_id, room_name, config, rated, started_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![config.room_id, config.room_name, config_json, rated, started_at as i64],
+        )?;
+        let game_id = tx.last_insert_rowid();
+        for (seat, info) in config.players.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO participants (game_id, player_id, name, is_robot, seat) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![game_id, info.id, info.name, info.id.starts_with("robot"), seat as i64],
+            )?;
+        }
+        tx.commit()?;
+        Ok(game_id)
+    }
+
+    pub fn write(&self, record: &Record) -> rusqlite::Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        let at = record.at_millis as i64;
+        for event in &record.events {
+            match event {
+                Event::TradeExecuted { buyer, seller, suit, price } => {
+                    tx.execute(
+                        "INSERT INTO trades (game_id, round_id, buyer, seller, suit, price, executed_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                        params![record.game_id, record.round, buyer, seller, suit.to_string(), price, at],
+                    )?;
+                }
+                Event::ChatMessage { from, to, text, .. } => {
+                    tx.execute(
+                        "INSERT INTO chat_messages (game_id, round_id, sender, recipient, text, sent_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![record.game_id, record.round, from, to, text, at],
+                    )?;
+                }
+                Event::RoundEnded { round_id, players, goal_suit, common_suit, .. } => {
+                    tx.execute(
+                        "INSERT OR REPLACE INTO rounds (game_id, round_id, goal_suit, common_suit, ended_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![record.game_id, round_id, goal_suit.to_string(), common_suit.to_string(), at],
+                    )?;
+                    for player in players {
+                        let goal_cards = player.hand.cards.get(goal_suit).copied().unwrap_or(0);
+                        tx.execute(
+                            "INSERT OR REPLACE INTO round_results (game_id, round_id, player_id, goal_cards, cash)
+                             VALUES (?1, ?2, ?3, ?4, ?5)",
+                            params![record.game_id, round_id, player.info.id, goal_cards, player.cash],
+                        )?;
+                    }
+                }
+                Event::GameEnded { players, completed } => {
+                    let mut ranked: Vec<_> = players.iter().collect();
+                    ranked.sort_by_key(|s| std::cmp::Reverse(s.cash));
+                    for player in &ranked {
+                        // 现金相同名次相同
+                        let rank = 1 + ranked.iter().filter(|p| p.cash > player.cash).count();
+                        tx.execute(
+                            "INSERT OR REPLACE INTO standings (game_id, player_id, rank, cash) VALUES (?1, ?2, ?3, ?4)",
+                            params![record.game_id, player.info.id, rank as i64, player.cash],
+                        )?;
+                    }
+                    tx.execute("UPDATE games SET ended_at = ?1 WHERE id = ?2", params![at, record.game_id])?;
+                    // 没打满回合就结束的对局不计分
+                    if !completed {
+                        tx.execute("UPDATE games SET rated = 0 WHERE id = ?1", [record.game_id])?;
+                    }
+                    update_ratings(&tx, record.game_id, at)?;
+                }
+                _ => {}
+            }
+        }
+        tx.commit()
+    }
+
+    pub fn game(&self, game_id: i64) -> rusqlite::Result<Option<GameRecord>> {
+        let conn = self.conn();
+        let header = conn
+            .query_row(
+                "SELECT room_id, room_name, started_at, ended_at FROM games WHERE id = ?1",
+                [game_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?, row.get::<_, Option<i64>>(3)?)),
+            )
+            .optional()?;
+        let Some((room_id, room_name, started_at, ended_at)) = header else {
+            return Ok(None);
+        };
+
+        let players = conn
+            .prepare("SELECT player_id, name, is_robot FROM participants WHERE game_id = ?1 ORDER BY seat")?
+            .query_map([game_id], |row| Ok(ParticipantRecord {
+                player_id: row.get(0)?,
+                name: row.get(1)?,
+                is_robot: row.get(2)?,
+            }))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut rounds = conn
+            .prepare("SELECT round_id, goal_suit, common_suit, ended_at FROM rounds WHERE game_id = ?1 ORDER BY round_id")?
+            .query_map([game_id], |row| Ok(RoundRecord {
+                round_id: row.get(0)?,
+                goal_suit: row.get(1)?,
+                common_suit: row.get(2)?,
+                ended_at: row.get::<_, i64>(3)? as u64,
+                results: vec![],
+            }))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut results = conn.prepare(
+            "SELECT r.player_id, r.goal_cards, r.cash FROM round_results r
+             JOIN participants p ON p.game_id = r.game_id AND p.player_id = r.player_id
+             WHERE r.game_id = ?1 AND r.round_id = ?2 ORDER BY p.seat",
+        )?;
+        for round in &mut rounds {
+            round.results = results
+                .query_map(params![game_id, round.round_id], |row| Ok(RoundResult {
+                    player_id: row.get(0)?,
+                    goal_cards: row.get(1)?,
+                    cash: row.get(2)?,
+                }))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+        }
+
+        let trades = conn
+            .prepare("SELECT round_id, buyer, seller, suit, price, executed_at FROM trades WHERE game_id = ?1 ORDER BY id")?
+            .query_map([game_id], |row| Ok(TradeRecord {
+                round_id: row.get(0)?,
+                buyer: row.get(1)?,
+                seller: row.get(2)?,
+                suit: row.get(3)?,
+                price: row.get(4)?,
+                executed_at: row.get::<_, i64>(5)? as u64,
+            }))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let chat = conn
+            .prepare("SELECT round_id, sender, recipient, text, sent_at FROM chat_messages WHERE game_id = ?1 ORDER BY id")?
+            .query_map([game_id], |row| Ok(ChatRecord {
+                round_id: row.get(0)?,
+                from: row.get(1)?,
+                to: row.get(2)?,
+                text: row.get(3)?,
+                sent_at: row.get::<_, i64>(4)? as u64,
+            }))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let standings = conn
+            .prepare("SELECT player_id, rank, cash FROM standings WHERE game_id = ?1 ORDER BY rank, player_id")?
+            .query_map([game_id], |row| Ok(Standing {
+                player_id: row.get(0)?,
+                rank: row.get(1)?,
+                cash: row.get(2)?,
+            }))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(Some(GameRecord {
+            id: game_id,
+            room_id,
+            room_name,
+            started_at: started_at as u64,
+            ended_at: ended_at.map(|t| t as u64),
+            players,
+            rounds,
+            trades,
+            chat,
+            standings,
+        }))
+    }
+
+    // 玩家参加过的游戏，新的在前
+    pub fn player_history(&self, player_id: &str, limit: u32) -> rusqlite::Result<Vec<HistoryEntry>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT g.id, g.room_id, g.room_name, g.started_at, g.ended_at,
+                    (SELECT COUNT(*) FROM participants c WHERE c.game_id = g.id),
+                    s.rank, s.cash
+             FROM participants p
+             JOIN games g ON g.id = p.game_id
+             LEFT JOIN standings s ON s.game_id = p.game_id AND s.player_id = p.player_id
+             WHERE p.player_id = ?1
+             ORDER BY g.started_at DESC, g.id DESC
+             LIMIT ?2",
+        )?;
+        stmt.query_map(params![player_id, limit], |row| Ok(HistoryEntry {
+            game_id: row.get(0)?,
+            room_id: row.get(1)?,
+            room_name: row.get(2)?,
+            started_at: row.get::<_, i64>(3)? as u64,
+            ended_at: row.get::<_, Option<i64>>(4)?.map(|t| t as u64),
+            players: row.get(5)?,
+            rank: row.get(6)?,
+            cash: row.get(7)?,
+        }))?
+        .collect()
+    }
+
+    pub fn leaderboard(&self, query: &LeaderboardQuery) -> rusqlite::Result<Vec<LeaderboardEntry>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT r.player_id, COALESCE(u.display_name, r.player_id), r.rating, COUNT(*), SUM(c.after - c.before)
+             FROM ratings r
+             JOIN rating_changes c ON c.ladder = r.ladder AND c.player_id = r.player_id
+             LEFT JOIN users u ON u.player_id = r.player_id
+             WHERE r.ladder = ?1 AND c.at >= ?2 AND (?3 IS NULL OR c.players = ?3)
+             GROUP BY r.player_id
+             ORDER BY r.rating DESC, r.player_id
+             LIMIT ?4",
+        )?;
+        let rows = stmt.query_map(
+            params![query.ladder.as_str(), query.since_millis as i64, query.players, query.limit],
+            |row| Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )?;
+        rows.enumerate()
+            .map(|(i, row)| {
+                let (player_id, display_name, rating, games, change) = row?;
+                Ok(LeaderboardEntry {
+                    rank: i as u32 + 1,
+                    is_robot: player_id.starts_with("robot"),
+                    player_id,
+                    display_name,
+                    rating,
+                    games,
+                    change,
+                })
+            })
+            .collect()
+    }
+
+    // 账号已存在时返回 false
+    pub fn create_user(&self, password_hash: &str, profile: &Profile, created_at: u64) -> rusqlite::Result<bool> {
+        let inserted = self.conn().execute(
+            "INSERT OR IGNORE INTO users (player_id, password_hash, display_name, avatar_url, email, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                profile.player_id,
+                password_hash,
+                profile.display_name,
+                profile.avatar_url,
+                profile.email,
+                created_at as i64
+            ],
+        )?;
+        Ok(inserted == 1)
+    }
+
+    pub fn user(&self, player_id: &str) -> rusqlite::Result<Option<UserRecord>> {
+        self.conn()
+            .query_row(
+                "SELECT password_hash, display_name, avatar_url, email FROM users WHERE player_id = ?1",
+                params![player_id],
+                |row| Ok(UserRecord {
+                    password_hash: row.get(0)?,
+                    profile: Profile {
+                        player_id: player_id.to_string(),
+                        display_name: row.get(1)?,
+                        avatar_url: row.get(2)?,
+                        email: row.get(3)?,
+                    },
+                }),
+            )
+            .optional()
+    }
+
+    pub fn update_profile(&self, profile: &Profile) -> rusqlite::Result<()> {
+        self.conn().execute(
+            "UPDATE users SET display_name = ?2, avatar_url = ?3, email = ?4 WHERE player_id = ?1",
+            params![profile.player_id, profile.display_name, profile.avatar_url, profile.email],
+        )?;
+        Ok(())
+    }
+}
+
+// 按最终名次更新积分，和结算写在同一个事务里
+fn update_ratings(tx: &Transaction, game_id: i64, at: i64) -> rusqlite::Result<()> {
+    // 不计分的对局跳过；同一局只计一次
+    let (rated, counted): (bool, bool) = tx.query_row(
+        "SELECT rated, EXISTS (SELECT 1 FROM rating_changes WHERE game_id = ?1) FROM games WHERE id = ?1",
+        [game_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    if !rated || counted {
+        return Ok(());
+    }
+    let standings = tx
+        .prepare(
+            "SELECT s.player_id, p.is_robot, s.rank FROM standings s
+             JOIN participants p ON p.game_id = s.game_id AND p.player_id = s.player_id
+             WHERE s.game_id = ?1",
+        )?
+        .query_map([game_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?, row.get::<_, u32>(2)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    if standings.len() < 2 {
+        return Ok(());
+    }
+
+    let ladder = Ladder::for_table(standings.iter().map(|(_, robot, _)| *robot));
+    let mut before = vec![];
+    for (player_id, _, rank) in &standings {
+        let rating: Option<f64> = tx
+            .query_row(
+                "SELECT rating FROM ratings WHERE ladder = ?1 AND player_id = ?2",
+                params![ladder.as_str(), player_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        before.push((rating.unwrap_or(INITIAL_RATING), *rank));
+    }
+    let after = rating::update(&before);
+
+    for (((player_id, _, _), (old, _)), new) in standings.iter().zip(&before).zip(&after) {
+        tx.execute(
+            "INSERT INTO ratings (ladder, player_id, rating, games) VALUES (?1, ?2, ?3, 1)
+             ON CONFLICT (ladder, player_id) DO UPDATE SET rating = excluded.rating, games = games + 1",
+            params![ladder.as_str(), player_id, new],
+        )?;
+        tx.execute(
+            "INSERT INTO rating_changes (game_id, ladder, player_id, players, before, after, at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![game_id, ladder.as_str(), player_id, standings.len() as i64, old, new, at],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::config_with_players;
+    use figgie_core::*;
+
+    fn config() -> GameConfig {
+        config_with_players("R1", &["alice", "robot1", "robot2", "robot3"])
+    }
+
+    #[test]
+    fn records_a_full_game() {
+        let store = Store::in_memory().unwrap();
+        let mut game = Game::new(config()).unwrap();
+        let game_id = store.create_game(&game.game_config, 1_000, true).unwrap();
+
+        game.start_round(1).unwrap();
+        // 直接构造一笔成交：alice 以 7 买入 robot1 的一张牌
+        let suit = *game.state.players[1].hand.cards.iter().find(|(_, c)| **c > 0).unwrap().0;
+        let mut events = game.place_quote(Quote { player_id: "robot1".to_string(), suit, side: Side::Offer, price: 7 }).unwrap();
+        events.extend(game.place_quote(Quote { player_id: "alice".to_string(), suit, side: Side::Bid, price: 7 }).unwrap());
+        events.extend(game.chat("alice".to_string(), Some("robot1".to_string()), "thanks".to_string()).unwrap());
+        events.extend(game.end_round().unwrap());
+        events.extend(game.end_game().unwrap());
+        store.write(&Record { game_id, round: 1, at_millis: 2_000, events }).unwrap();
+
+        let record = store.game(game_id).unwrap().unwrap();
+        assert_eq!(record.players.len(), 4);
+        assert!(record.players[1].is_robot && !record.players[0].is_robot);
+        assert_eq!(record.trades.len(), 1);
+        assert_eq!((record.trades[0].buyer.as_str(), record.trades[0].price), ("alice", 7));
+        assert_eq!(record.chat.len(), 1);
+        assert_eq!((record.chat[0].from.as_str(), record.chat[0].to.as_deref()), ("alice", Some("robot1")));
+        assert_eq!(record.rounds.len(), 1);
+        assert_eq!(record.rounds[0].results.len(), 4);
+        assert_eq!(record.standings.len(), 4);
+        assert_eq!(record.standings[0].rank, 1);
+        assert_eq!(record.ended_at, Some(2_000));
+
+        let history = store.player_history("alice", 10).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].game_id, game_id);
+        assert_eq!(history[0].players, 4);
+        assert!(history[0].rank.is_some());
+
+        assert!(store.game(game_id + 1).unwrap().is_none());
+        assert!(store.player_history("nobody", 10).unwrap().is_empty());
+    }
+
+    // 打完 rounds 个计时走完的回合后结束并写库，名次由随机发牌决定
+    fn play_rounds(store: &Store, ids: &[&str], at_millis: u64, rated: bool, rounds: u32) -> i64 {
+        let mut config = config();
+        config.players = ids.iter().map(|id| Info { id: id.to_string(), name: id.to_string() }).collect();
+        config.ante_amount = config.pot / ids.len() as u32;
+        let full = std::time::Duration::from_secs(config.trading_duration_secs as u64);
+        let clock = Arc::new(ManualClock::new(std::time::Duration::ZERO));
+        let mut game = Game::with_clock(config, clock.clone()).unwrap();
+        let game_id = store.create_game(&game.game_config, at_millis, rated).unwrap();
+        let mut events = vec![];
+        for round in 1..=rounds {
+            events.extend(game.start_round(round).unwrap());
+            clock.advance(full);
+            events.extend(game.end_round().unwrap());
+        }
+        events.extend(game.end_game().unwrap());
+        store.write(&Record { game_id, round: rounds, at_millis, events }).unwrap();
+        game_id
+    }
+
+    fn play(store: &Store, ids: &[&str], at_millis: u64, rated: bool) {
+        play_rounds(store, ids, at_millis, rated, DEFAULT_ROUNDS);
+    }
+
+    #[test]
+    fn rates_games_on_separate_ladders() {
+        let store = Store::in_memory().unwrap();
+        play(&store, &["alice", "bob", "carol", "dave"], 1_000, true);
+        play(&store, &["alice", "bob", "carol", "dave", "erin"], 5_000, true);
+        play(&store, &["alice", "robot1", "robot2", "robot3"], 5_000, true);
+        // 不计分的对局不影响积分
+        play(&store, &["alice", "bob", "carol", "frank"], 6_000, false);
+
+        let human = store.leaderboard(&LeaderboardQuery { limit: 10, ..Default::default() }).unwrap();
+        assert_eq!(human.len(), 5);
+        assert!(human.iter().all(|e| !e.is_robot));
+        assert!(human.windows(2).all(|w| w[0].rating >= w[1].rating));
+        assert_eq!(human.iter().find(|e| e.player_id == "alice").unwrap().games, 2);
+        // 积分总和守恒：新玩家以初始分加入
+        let total: f64 = human.iter().map(|e| e.rating).sum();
+        assert!((total - 5.0 * INITIAL_RATING).abs() < 1e-6);
+
+        let five = store.leaderboard(&LeaderboardQuery { players: Some(5), limit: 10, ..Default::default() }).unwrap();
+        assert_eq!(five.len(), 5);
+        assert!(five.iter().all(|e| e.games == 1));
+        let recent = store.leaderboard(&LeaderboardQuery { since_millis: 2_000, limit: 10, ..Default::default() }).unwrap();
+        assert_eq!(recent.len(), 5);
+        assert!(recent.iter().all(|e| e.games == 1));
+
+        let mixed = store.leaderboard(&LeaderboardQuery { ladder: Ladder::Mixed, limit: 2, ..Default::default() }).unwrap();
+        assert_eq!(mixed.len(), 2);
+        assert_eq!(mixed[1].rank, 2);
+    }
+
+    #[test]
+    fn unfinished_games_are_not_rated() {
+        let store = Store::in_memory().unwrap();
+        let game_id = play_rounds(&store, &["alice", "bob", "carol", "dave"], 1_000, true, DEFAULT_ROUNDS - 1);
+
+        let rated: bool = store.conn().query_row("SELECT rated FROM games WHERE id = ?1", [game_id], |row| row.get(0)).unwrap();
+        assert!(!rated);
+        assert!(store.leaderboard(&LeaderboardQuery { limit: 10, ..Default::default() }).unwrap().is_empty());
+        // 名次照常记录
+        assert_eq!(store.game(game_id).unwrap().unwrap().standings.len(), 4);
+    }
+
+    #[test]
+    fn stores_users_and_profiles() {
+        let store = Store::in_memory().unwrap();
+        let mut profile = Profile {
+            player_id: "alice".to_string(),
+            display_name: "Alice".to_string(),
+            ..Profile::default()
+        };
+        assert!(store.create_user("hash", &profile, 1).unwrap());
+        assert!(!store.create_user("other", &profile, 2).unwrap());
+
+        profile.avatar_url = Some("https://example.com/a.png".to_string());
+        profile.email = Some("alice@example.com".to_string());
+        store.update_profile(&profile).unwrap();
+
+        let user = store.user("alice").unwrap().unwrap();
+        assert_eq!(user.password_hash, "hash");
+        assert_eq!(user.profile.email.as_deref(), Some("alice@example.com"));
+        assert!(store.user("bob").unwrap().is_none());
+    }
+}
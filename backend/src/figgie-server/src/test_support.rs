@@ -0,0 +1,30 @@
+// 各模块测试共用的房间配置和对局
+use figgie_core::*;
+
+// p0..p3 四个座位的标准房间
+pub fn config(room_id: &str) -> GameConfig {
+    config_with_players(room_id, &["p0", "p1", "p2", "p3"])
+}
+
+// 指定座位的房间，名字取 id 的大写
+pub fn config_with_players(room_id: &str, players: &[&str]) -> GameConfig {
+    GameConfig {
+        room_name: "test room".to_string(),
+        room_id: room_id.to_string(),
+        players: players.iter().map(|id| Info { id: id.to_string(), name: id.to_uppercase() }).collect(),
+        starting_cash: 350,
+        ante_amount: 200u32.checked_div(players.len() as u32).unwrap_or(0),
+        pot: 200,
+        trading_duration_secs: 240,
+        deck: DeckConfig::default(),
+        leftover_policy: LeftoverPolicy::default(),
+        rounds: DEFAULT_ROUNDS,
+    }
+}
+
+// 已经开始第一轮的对局
+pub fn game(room_id: &str) -> Game {
+    let mut game = Game::new(config(room_id)).unwrap();
+    game.start_round(1).unwrap();
+    game
+}
@@ -1,32 +1,48 @@
-use serde::Deserialize;
-use figgie_core::{Action, Quote, Suit, Side};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use figgie_core::{Action, Quote, Suit, Side, DeckConfig, LeftoverPolicy};
+use crate::dispatcher::AbsentQuotePolicy;
+use crate::limits::FloodLimits;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PlayerInfo {
     pub id: String,
     pub name: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct StartGameRequest {
     pub room_name: String,
     pub room_id: String,
     pub players: Vec<PlayerInfo>,
+    // 以下为可选的变体规则，缺省时使用配置文件 [rules] 中的默认值
+    pub starting_cash: Option<u32>,
+    pub pot: Option<u32>,
+    pub trading_duration_secs: Option<u32>,
+    pub rounds: Option<u32>,
+    pub deck: Option<DeckConfig>,
+    pub leftover_policy: Option<LeftoverPolicy>,
+    // 玩家掉线后是否撤掉其挂单，缺省保留
+    pub absent_quote_policy: Option<AbsentQuotePolicy>,
+    // 是否计入积分，缺省计入
+    pub rated: Option<bool>,
+    // 本房间的入站限流，缺省使用配置文件 [limits]
+    pub limits: Option<FloodLimits>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", content = "payload")]
 pub enum ActionView {
     PlaceQuote {
         player_id: String,
-        suit: String,
-        side: String,
+        suit: Suit,
+        side: Side,
         price: u32,
     },
     CancelQuote {
         player_id: String,
-        suit: String,
-        side: String,
+        suit: Suit,
+        side: Side,
         price: u32,
     },
     StartRound {
@@ -41,50 +57,65 @@ pub enum ActionView {
         round_id: u32,
         room_id: String,
         player_id: String,
-    }
+    },
+    Pause {
+        room_id: String,
+    },
+    Resume {
+        room_id: String,
+    },
+    // to 为空时发给全桌
+    Chat {
+        player_id: String,
+        #[serde(default)]
+        to: Option<String>,
+        text: String,
+    },
 }
 
-use std::convert::TryFrom;
-
-impl TryFrom<ActionView> for Action {
-    type Error = &'static str;
-
-    fn try_from(view: ActionView) -> Result<Self, Self::Error> {
+impl From<ActionView> for Action {
+    fn from(view: ActionView) -> Self {
         match view {
             ActionView::PlaceQuote { player_id, suit, side, price } => {
-                let suit = Suit::from_str(&suit)?;
-                let side = Side::from_str(&side)?;
-
-                Ok(Action::PlaceQuote(Quote {
-                    player_id: player_id,
+                Action::PlaceQuote(Quote {
+                    player_id,
                     suit,
                     side,
                     price,
-                }))
+                })
             }
 
             ActionView::CancelQuote { player_id, suit, side, price } => {
-                let suit = Suit::from_str(&suit)?;
-                let side = Side::from_str(&side)?;
-
-                Ok(Action::CancelQuote(Quote {
-                    player_id: player_id,
+                Action::CancelQuote(Quote {
+                    player_id,
                     suit,
                     side,
                     price,
-                }))
+                })
+            }
+
+            ActionView::EndGame { .. } => {
+                Action::EndGame
+            }
+
+            ActionView::EndRound { .. } => {
+                Action::EndRound
+            }
+
+            ActionView::Pause { .. } => {
+                Action::Pause
             }
 
-            ActionView::EndGame { round_id, room_id, player_id } => {
-                Ok(Action::EndGame)
+            ActionView::Resume { .. } => {
+                Action::Resume
             }
 
-            ActionView::EndRound { round_id, room_id } => {
-                Ok(Action::EndRound)
+            ActionView::StartRound { round_id, .. } => {
+                Action::StartRound(round_id)
             }
 
-            ActionView::StartRound { round_id, room_id } => {
-                Ok(Action::StartRound(round_id))
+            ActionView::Chat { player_id, to, text } => {
+                Action::Chat { player_id, to, text }
             }
         }
     }
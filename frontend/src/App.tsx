@@ -1,14 +1,25 @@
-import { Link, Navigate, Route, Routes, useLocation } from 'react-router-dom';
+import { Link, Navigate, Route, Routes, useLocation, useNavigate } from 'react-router-dom';
 import { useState } from 'react';
 import Home from './pages/Home';
 import Game from './pages/Game';
 import Room from './pages/Room';
+import Login from './pages/Login';
+import { clearSession, loadSession } from './config/auth';
 import './App.css';
 
 function App() {
   const location = useLocation();
   const hideTopbar = location.pathname.startsWith('/game');
   const [isMenuOpen, setIsMenuOpen] = useState(false);
+  const navigate = useNavigate();
+  // 每次切换页面时重新读取，登录或退出后导航栏随之更新
+  const session = loadSession();
+
+  const handleLogout = () => {
+    clearSession();
+    setIsMenuOpen(false);
+    navigate('/');
+  };
 
   return (
     <div className="layout">
@@ -37,6 +48,13 @@ function App() {
               <Link to="/" className="nav-link">历史对局</Link>
               <Link to="/" className="nav-link">如何游玩</Link>
               <Link to="/" className="nav-link">游戏设置</Link>
+              {session ? (
+                <a className="nav-link" onClick={handleLogout} style={{ cursor: 'pointer' }}>
+                  退出（{session.profile.display_name}）
+                </a>
+              ) : (
+                <Link to="/login" className="nav-link">登录</Link>
+              )}
             </div>
             <button
               className="menu-toggle"
@@ -53,6 +71,11 @@ function App() {
                 <Link to="/" className="nav-link" onClick={() => setIsMenuOpen(false)}>历史对局</Link>
                 <Link to="/" className="nav-link" onClick={() => setIsMenuOpen(false)}>如何游玩</Link>
                 <Link to="/" className="nav-link" onClick={() => setIsMenuOpen(false)}>游戏设置</Link>
+                {session ? (
+                  <a className="nav-link" onClick={handleLogout} style={{ cursor: 'pointer' }}>退出</a>
+                ) : (
+                  <Link to="/login" className="nav-link" onClick={() => setIsMenuOpen(false)}>登录</Link>
+                )}
               </div>
             )}
           </nav>
@@ -62,6 +85,7 @@ function App() {
       <main className="content">
         <Routes>
           <Route path="/" element={<Home />} />
+          <Route path="/login" element={<Login />} />
           <Route path="/room" element={<Room />} />
           <Route path="/game" element={<Game />} />
           <Route path="*" element={<Navigate to="/" replace />} />
@@ -0,0 +1,32 @@
+import type { Session } from "../types/player";
+
+// 登录状态保存在 localStorage，刷新页面后仍然有效
+const SESSION_KEY = "figgie.session";
+
+export function loadSession(): Session | null {
+  try {
+    const raw = localStorage.getItem(SESSION_KEY);
+    return raw ? (JSON.parse(raw) as Session) : null;
+  } catch {
+    return null;
+  }
+}
+
+export function saveSession(session: Session) {
+  localStorage.setItem(SESSION_KEY, JSON.stringify(session));
+}
+
+export function clearSession() {
+  localStorage.removeItem(SESSION_KEY);
+}
+
+// REST 接口用 Authorization 头携带 token
+export function authHeaders(session: Session): Record<string, string> {
+  return { Authorization: `Bearer ${session.token}` };
+}
+
+// 浏览器的 WebSocket 不能设置请求头，token 放在 URL 参数里
+export function withToken(url: string, session: Session): string {
+  const sep = url.includes("?") ? "&" : "?";
+  return `${url}${sep}token=${encodeURIComponent(session.token)}`;
+}
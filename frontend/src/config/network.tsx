@@ -1 +1,6 @@
-export const WS_BASE_URL = "ws://localhost:8080";
\ No newline at end of file
+// 开发时直连本机后端；由 figgie-server 托管时连接同一个站点
+const WS_SCHEME = window.location.protocol === "https:" ? "wss:" : "ws:";
+
+export const WS_BASE_URL = import.meta.env.DEV
+  ? "ws://localhost:8080"
+  : `${WS_SCHEME}//${window.location.host}`;
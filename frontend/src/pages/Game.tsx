@@ -1,6 +1,7 @@
 import { useEffect, useRef, useState, useMemo } from "react";
 import { useLocation, useNavigate } from "react-router-dom";
 import { WS_BASE_URL } from "../config/network";
+import { loadSession, withToken } from "../config/auth";
 import type {
   Suit,
   PlayerState,
@@ -19,55 +20,40 @@ import "./Game.css";
 const PLAYER_COLORS = ["#7293e6", "#75c9a3", "#e9c17d", "#c8a5e6", "#ee9fa7"];
 const GAME_TIME = 240;
 
-// 倒计时组件，接收一个 UNIX 时间戳作为起始，4分钟倒计时
+// 倒计时组件，只负责显示；回合由服务端按时结算，暂停时停在 frozenSeconds
 function CountdownBox({
-  startTimestamp,
-  endRound,
+  deadline,
+  frozenSeconds,
 }: {
-  startTimestamp: number;
-  endRound?: () => void;
+  // 回合结束的本地时间戳（毫秒）
+  deadline: number;
+  frozenSeconds: number | null;
 }) {
   const [secondsLeft, setSecondsLeft] = useState(0);
   // 浏览器环境使用 number 作为定时器标识，避免依赖 NodeJS 类型
   const intervalRef = useRef<number | null>(null);
-  const hasEndedRef = useRef(false);
-  const endRoundRef = useRef(endRound);
 
   useEffect(() => {
-    endRoundRef.current = endRound;
-  }, [endRound]);
-
-  useEffect(() => {
-    // 立即计一次
-    hasEndedRef.current = false;
-
     function update() {
-      const now = Math.floor(Date.now() / 1000);
-      // 剩余秒数
-      const left = Math.max(0, GAME_TIME - (now - startTimestamp));
-      setSecondsLeft(left);
-      if (left <= 0 && !hasEndedRef.current) {
-        hasEndedRef.current = true;
-
-        if (intervalRef.current) {
-          clearInterval(intervalRef.current);
-          intervalRef.current = null;
-        }
-        endRoundRef.current?.();
+      if (frozenSeconds !== null) {
+        setSecondsLeft(frozenSeconds);
+        return;
       }
+      setSecondsLeft(Math.max(0, Math.ceil((deadline - Date.now()) / 1000)));
     }
 
     if (intervalRef.current !== null) {
       clearInterval(intervalRef.current);
     }
     intervalRef.current = window.setInterval(update, 1000);
+    // 立即计一次
     update();
 
     return () => {
       if (intervalRef.current !== null) clearInterval(intervalRef.current);
       intervalRef.current = null;
     };
-  }, [startTimestamp]);
+  }, [deadline, frozenSeconds]);
 
   const min = Math.floor(secondsLeft / 60);
   const sec = secondsLeft % 60;
@@ -80,10 +66,9 @@ function CountdownBox({
 }
 
 export default function FiggieTradingPage() {
-  // 假设传入 unix startTimestamp，演示初始为页面打开那刻
-  const [startTimestamp, setStartTimeStamp] = useState(() =>
-    Math.floor(Date.now() / 1000)
-  );
+  // 第一回合在开房时就已开始，先按页面打开的时刻估算，收到服务端事件后校正
+  const [deadline, setDeadline] = useState(() => Date.now() + GAME_TIME * 1000);
+  const [frozenSeconds, setFrozenSeconds] = useState<number | null>(null);
   const [round, setRound] = useState(1);
   const [showEndDialog, setShowEndDialog] = useState(false);
   const [showRoundEndDialog, setShowRoundEndDialog] = useState(false);
@@ -97,7 +82,8 @@ export default function FiggieTradingPage() {
   const { state } = useLocation();
 
   const payload = state?.payload;
-  if (!payload) {
+  const session = loadSession();
+  if (!payload || !session) {
     return <div>非法进入 game 页面</div>;
   }
   const playersCount = payload.players.length;
@@ -194,7 +180,9 @@ export default function FiggieTradingPage() {
   };
 
   useEffect(() => {
-    const ws = new WebSocket(`${WS_BASE_URL}/ws/${room_id}/${player_id}`);
+    const ws = new WebSocket(
+      withToken(`${WS_BASE_URL}/ws/${room_id}/${player_id}`, session)
+    );
     wsRef.current = ws;
 
     ws.onopen = () => {
@@ -287,9 +275,27 @@ export default function FiggieTradingPage() {
             Heart: player.hand.Heart ?? 0,
           });
           setRound(round_id);
+          setDeadline(Date.now() + GAME_TIME * 1000);
+          setFrozenSeconds(null);
+        }
+        if (data.type === "TradingPaused") {
+          setFrozenSeconds(Math.ceil(data.payload.remaining_millis / 1000));
+        }
+        if (data.type === "TradingResumed") {
+          setDeadline(Date.now() + data.payload.remaining_millis);
+          setFrozenSeconds(null);
         }
         if (data.type === "RoundEnded") {
           const payload = data.payload;
+          // 交易时间到由服务端结算，这里只负责展示结果
+          setSuitPrices({
+            Spade: { seller: "", ask: 0, buyer: "", bid: 0 },
+            Club: { seller: "", ask: 0, buyer: "", bid: 0 },
+            Diamond: { seller: "", ask: 0, buyer: "", bid: 0 },
+            Heart: { seller: "", ask: 0, buyer: "", bid: 0 },
+          });
+          setFrozenSeconds(0);
+          setShowRoundEndDialog(true);
           setGoalSuit(suitToSymbol(payload.goal_suit));
           if (payload.round_id !== round) {
             console.log("Round ID don't match");
@@ -332,26 +338,6 @@ export default function FiggieTradingPage() {
     };
   }, [room_id, player_id]);
 
-  const handleRoundEnd = async () => {
-    const action: Action = {
-      type: "EndRound",
-      payload: {
-        round_id: round,
-        room_id: room_id,
-      },
-    };
-    if (wsRef.current) {
-      wsRef.current.send(JSON.stringify(action));
-      setSuitPrices({
-        Spade: { seller: "", ask: 0, buyer: "", bid: 0 },
-        Club: { seller: "", ask: 0, buyer: "", bid: 0 },
-        Diamond: { seller: "", ask: 0, buyer: "", bid: 0 },
-        Heart: { seller: "", ask: 0, buyer: "", bid: 0 },
-      });
-      setShowRoundEndDialog(true);
-    }
-  };
-
   const handleNextRound = async () => {
     const action: Action = {
       type: "StartRound",
@@ -363,7 +349,6 @@ export default function FiggieTradingPage() {
     if (wsRef.current) {
       wsRef.current.send(JSON.stringify(action));
       setShowRoundEndDialog(false);
-      setStartTimeStamp(() => Math.floor(Date.now() / 1000));
       setPlayerStates((prev) =>
         prev.map((p) => ({
           ...p,
@@ -404,10 +389,7 @@ export default function FiggieTradingPage() {
     <div className="page">
       {/* Header */}
       <header className="header">
-        <CountdownBox
-          startTimestamp={startTimestamp}
-          endRound={handleRoundEnd}
-        />
+        <CountdownBox deadline={deadline} frozenSeconds={frozenSeconds} />
         <div style={{ textAlign: "center" }}>
           <div style={{ fontWeight: 600 }}>{payload.room_name}</div>
         </div>
@@ -0,0 +1,156 @@
+import React, { useState } from "react";
+import { useLocation, useNavigate } from "react-router-dom";
+import type { Session } from "../types/player";
+import { saveSession } from "../config/auth";
+
+type Mode = "login" | "register";
+
+const inputStyle: React.CSSProperties = {
+  fontSize: "1rem",
+  padding: "10px 14px",
+  border: "1.5px solid #d6e4fa",
+  borderRadius: 10,
+  outline: "none",
+  color: "#24385b",
+  background: "#f8fbff",
+};
+
+const Login: React.FC = () => {
+  const [mode, setMode] = useState<Mode>("login");
+  const [playerId, setPlayerId] = useState("");
+  const [password, setPassword] = useState("");
+  const [displayName, setDisplayName] = useState("");
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const navigate = useNavigate();
+  const { state } = useLocation();
+  // 登录后回到之前想去的页面，默认进入房间
+  const from: string = state?.from ?? "/room";
+
+  // handleSubmit: 向后端 /api/auth/login 或 /api/auth/register 发 POST 请求
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setLoading(true);
+    setError(null);
+
+    const payload =
+      mode === "login"
+        ? { player_id: playerId, password }
+        : { player_id: playerId, password, display_name: displayName || null };
+    try {
+      const resp = await fetch(`/api/auth/${mode}`, {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify(payload),
+      });
+      const data = await resp.json();
+      if (!resp.ok) {
+        setError(data.error ?? `HTTP ${resp.status}`);
+        setLoading(false);
+        return;
+      }
+      saveSession(data as Session);
+      navigate(from, { replace: true });
+    } catch {
+      setError("网络异常，请稍后再试");
+      setLoading(false);
+    }
+  };
+
+  return (
+    <section
+      style={{
+        padding: "24px 24px",
+        width: "80vw",
+        maxWidth: 420,
+        minWidth: 300,
+        margin: "12vh auto 0",
+        display: "flex",
+        flexDirection: "column",
+        gap: 18,
+      }}
+    >
+      <h2 style={{ color: "#284179", margin: 0, textAlign: "center" }}>
+        {mode === "login" ? "登录" : "注册账号"}
+      </h2>
+      <form
+        onSubmit={handleSubmit}
+        style={{ display: "flex", flexDirection: "column", gap: 14 }}
+      >
+        <input
+          value={playerId}
+          onChange={(e) => setPlayerId(e.target.value)}
+          placeholder="玩家 ID"
+          autoComplete="username"
+          style={inputStyle}
+          maxLength={32}
+          required
+        />
+        <input
+          type="password"
+          value={password}
+          onChange={(e) => setPassword(e.target.value)}
+          placeholder="密码"
+          autoComplete={mode === "login" ? "current-password" : "new-password"}
+          style={inputStyle}
+          required
+        />
+        {mode === "register" && (
+          <input
+            value={displayName}
+            onChange={(e) => setDisplayName(e.target.value)}
+            placeholder="昵称（可选）"
+            style={inputStyle}
+            maxLength={24}
+          />
+        )}
+        <button
+          type="submit"
+          disabled={loading}
+          style={{
+            padding: "12px 0",
+            background: "linear-gradient(90deg, #71b7fa 0%, #6a93ec 100%)",
+            border: "none",
+            color: "#fff",
+            borderRadius: 24,
+            fontSize: "1.05rem",
+            fontWeight: 700,
+            cursor: loading ? "not-allowed" : "pointer",
+            opacity: loading ? 0.6 : 1,
+          }}
+        >
+          {loading ? "请稍候..." : mode === "login" ? "登录" : "注册"}
+        </button>
+      </form>
+      <button
+        onClick={() => {
+          setMode(mode === "login" ? "register" : "login");
+          setError(null);
+        }}
+        style={{
+          border: "none",
+          background: "transparent",
+          color: "#386be6",
+          cursor: "pointer",
+          fontSize: 15,
+        }}
+      >
+        {mode === "login" ? "没有账号？注册一个" : "已有账号？去登录"}
+      </button>
+      {/* 错误提示 */}
+      <div
+        style={{
+          minHeight: 25,
+          color: "#dc6777",
+          textAlign: "center",
+          fontSize: 15,
+        }}
+      >
+        {error}
+      </div>
+    </section>
+  );
+};
+
+export default Login;
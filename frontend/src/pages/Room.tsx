@@ -1,12 +1,10 @@
 import React, { useState } from "react";
-import { useNavigate } from "react-router-dom";
+import { Navigate, useNavigate } from "react-router-dom";
 import type { Player } from "../types/player";
 import robotAvatar from "../assets/robot.svg";
-import {
-  defaultRoomName,
-  defaultRoomID,
-  currentPlayer,
-} from "../constants/dev";
+import playerAvatar from "../assets/player.svg";
+import { defaultRoomName } from "../constants/dev";
+import { authHeaders, clearSession, loadSession } from "../config/auth";
 
 // 纯函数，不适合放在内部（会增加渲染开销），建议放在外部
 const randomRobot = (i: number): Player => ({
@@ -15,10 +13,17 @@ const randomRobot = (i: number): Player => ({
   avatar: robotAvatar,
 });
 
+// 每次进入页面生成新的房间号，服务端对重复的房间号返回 409
+const newRoomId = () =>
+  `R${Math.random().toString(36).slice(2, 8).toUpperCase()}`;
+
 const Room: React.FC = () => {
   // state 定义
-  const [roomName, setRoomName] = useState(defaultRoomName);
-  const [roomId] = useState(defaultRoomID);
+  const [session] = useState(loadSession);
+  const [roomName, setRoomName] = useState(() =>
+    session ? `${session.profile.display_name}的房间` : defaultRoomName
+  );
+  const [roomId] = useState(newRoomId);
   // 这里的 players 只包含机器人，不包含玩家自己
   const [players, setPlayers] = useState<Player[]>([]);
   const [copied, setCopied] = useState(false);
@@ -63,6 +68,18 @@ const Room: React.FC = () => {
     setIsReady((r) => !r);
   };
 
+  // 未登录时先去登录，开房和连接都需要 token
+  if (!session) {
+    return <Navigate to="/login" state={{ from: "/room" }} replace />;
+  }
+
+  // 当前玩家即登录的账号
+  const currentPlayer: Player = {
+    id: session.profile.player_id,
+    name: session.profile.display_name,
+    avatar: session.profile.avatar_url ?? playerAvatar,
+  };
+
   // handleStartGame: 向后端 /api/start 接口发 POST 请求
   const handleStartGame = async () => {
     if (!canStart) return;
@@ -81,12 +98,21 @@ const Room: React.FC = () => {
       console.log(payload);
       const resp = await fetch("/api/start", {
         method: "POST",
-        headers: { "Content-Type": "application/json" },
+        headers: { "Content-Type": "application/json", ...authHeaders(session) },
         body: JSON.stringify(payload),
       });
       console.log(resp);
+      // token 过期或失效，重新登录
+      if (resp.status === 401) {
+        clearSession();
+        navigate("/login", { state: { from: "/room" } });
+        return;
+      }
       if (!resp.ok) {
-        throw new Error(`HTTP ${resp.status}`);
+        const data = await resp.json().catch(() => ({}));
+        setStartError(data.error ?? `游戏无法启动（HTTP ${resp.status}）`);
+        setStartLoading(false);
+        return;
       }
       navigate("/game", {
         state: {
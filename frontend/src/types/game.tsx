@@ -109,11 +109,25 @@ export interface RoundEndedPayload {
   round_id: number;
   server_time: number;
   goal_suit: Suit;
+  common_suit: Suit;
+  // 本轮每种花色发出的牌数
+  suit_counts: Hand;
   players: Player[];
+  // 每个玩家的初始手牌，key 为 player id
+  starting_hands: Record<string, Hand>;
+}
+
+// 暂停和恢复时回合剩余的交易时间，暂停期间计时不走
+export interface TradingPausePayload {
+  round_id: number;
+  server_time: number;
+  remaining_millis: number;
 }
 
 export interface GameEndedPayload {
   players: Player[];
+  // 是否打满了配置的回合数；没打满的对局不计分
+  completed: boolean;
 }
 
 export type Event =
@@ -122,4 +136,6 @@ export type Event =
   | { type: "QuotePlaced"; payload: QuotePlacedPayload }
   | { type: "RoundStarted"; payload: RoundStartedPayload }
   | { type: "RoundEnded"; payload: RoundEndedPayload }
+  | { type: "TradingPaused"; payload: TradingPausePayload }
+  | { type: "TradingResumed"; payload: TradingPausePayload }
   | { type: "GameEnded"; payload: GameEndedPayload }
\ No newline at end of file
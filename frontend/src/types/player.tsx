@@ -4,4 +4,17 @@ export type Player = {
   id: string;
   name: string;
   avatar: string;
-};
\ No newline at end of file
+};
+
+// 后端账号资料，对应 /api/auth/login 和 /api/auth/register 的返回
+export type Profile = {
+  player_id: string;
+  display_name: string;
+  avatar_url: string | null;
+  email: string | null;
+};
+
+export type Session = {
+  token: string;
+  profile: Profile;
+};